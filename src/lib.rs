@@ -6,12 +6,22 @@
 //! println!("Got deadly signal {}", sigs.next().unwrap());
 //! # }
 //! ```
-#![cfg_attr(not(feature = "tokio"), no_std)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
 #[cfg_attr(target_os = "linux", path = "linux.rs")]
 mod os;
 
-pub use os::{Signal, Signals};
+pub use os::{
+   chain_sigint, die_by, queue, queue_ptr, send, send_to_group, send_to_thread, sigint_workaround,
+   sigwait, Backend, Drain, Errno, Error, Infos, PidFd, RtSignal, SigSet, SigSetIter,
+   SigintWorkaround, Signal, SignalInfo, Signals, UnblockedThreads,
+};
 
+#[cfg(feature = "std")]
+pub use os::init;
 #[cfg(feature = "tokio")]
 pub use os::tokio;
+#[cfg(feature = "std")]
+pub use os::{
+   ChildReaper, ChildStatus, CommandExt, Draining, Policy, Reaped, Shutdown, ShutdownToken,
+};
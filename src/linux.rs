@@ -1,218 +1,1161 @@
+#[cfg(feature = "std")]
+#[path = "linux/command.rs"]
+mod command;
+#[path = "linux/error.rs"]
+mod error;
+#[path = "linux/handlers.rs"]
+mod handlers;
+#[path = "linux/info.rs"]
+mod info;
+#[cfg(feature = "std")]
+#[path = "linux/init.rs"]
+pub mod init;
+#[path = "linux/mask.rs"]
+mod mask;
+#[cfg(feature = "std")]
+#[path = "linux/reaper.rs"]
+mod reaper;
+#[path = "linux/ring.rs"]
+mod ring;
+#[path = "linux/send.rs"]
+mod send;
+#[cfg(feature = "std")]
+#[path = "linux/shutdown.rs"]
+mod shutdown;
+#[path = "linux/signal.rs"]
+mod signal;
+#[path = "linux/sigset.rs"]
+mod sigset;
+#[path = "linux/sigwait.rs"]
+pub mod sigwait;
+#[path = "linux/sys.rs"]
+mod sys;
+#[path = "linux/threads.rs"]
+mod threads;
 #[cfg(feature = "tokio")]
 #[path = "linux/tokio.rs"]
 pub mod tokio;
 
-use core::mem::{size_of_val, MaybeUninit};
-use core::ptr;
-use core::sync::atomic::{AtomicI32, Ordering};
+use core::cell::UnsafeCell;
+use core::mem::{self, size_of_val, MaybeUninit};
+use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU8, AtomicUsize, Ordering};
+use core::time::Duration;
+use core::{hint, ptr};
+#[cfg(feature = "std")]
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+#[cfg(feature = "std")]
+use std::time::Instant;
 
-use heveanly::syscall::{self, syscall, Check};
-use heveanly::{retry_eintr, AsUninitBytes, Fd};
 use libc::sigemptyset;
+use ring::Ring;
+use sys::{retry_eintr, AsUninitBytes, Fd, EAGAIN, EINTR};
 
-pub type Signal = libc::c_int;
+#[cfg(feature = "std")]
+pub use command::CommandExt;
+pub use error::Error;
+pub use info::SignalInfo;
+#[cfg(feature = "std")]
+pub use reaper::{ChildReaper, ChildStatus, Reaped};
+pub use send::{queue, queue_ptr, send, send_to_group, send_to_thread, PidFd};
+#[cfg(feature = "std")]
+pub use shutdown::{Draining, Policy, ShutdownToken};
+#[cfg(feature = "std")]
+pub type Shutdown = shutdown::Shutdown;
+pub use signal::{RtSignal, Signal};
+pub use sigset::{SigSet, SigSetIter};
+pub use sys::Errno;
+pub use threads::UnblockedThreads;
 
-fn signals_new<T>(sigs: &[Signal], from_sigset: fn(&mut libc::sigset_t) -> T) -> T {
-   let mut sigset = MaybeUninit::uninit();
-   from_sigset(unsafe {
-      libc::sigemptyset(sigset.as_mut_ptr());
-      for &sig in sigs {
-         libc::sigaddset(sigset.as_mut_ptr(), sig);
+// The only reason pretty much anything in here can fail is if system resources
+// are exhausted, so if Rust can panic on OOM then I can too. :))) The `try_`
+// constructors are there for anyone who'd rather not.
+fn unwrap<T>(r: Result<T, Error>) -> T {
+   match r {
+      Ok(x) => x,
+      Err(e) => panic!("{e}"),
+   }
+}
+
+macro_rules! libc_try {
+   ($f:ident, $($xs:expr),*$(,)?) => {
+      {
+         let rc = libc::$f($($xs),+);
+         if rc < 0 {
+            return Err(Error::last_os(stringify!($f)));
+         }
+         rc
       }
-      sigset.assume_init_mut()
-   })
+   }
 }
 
-fn fill_all(sigs: *mut libc::sigset_t) {
-   unsafe {
-      libc::sigfillset(sigs);
-      // From the `sigprocmask` man page:
-      // > If SIGBUS, SIGFPE, SIGILL, or SIGSEGV are generated while they are
-      // > blocked, the result is undefined, unless the signal was generated by
-      // > kill(2), sigqueue(3), or raise(3).
-      libc::sigdelset(sigs, libc::SIGBUS);
-      libc::sigdelset(sigs, libc::SIGFPE);
-      libc::sigdelset(sigs, libc::SIGILL);
-      libc::sigdelset(sigs, libc::SIGSEGV);
-      // The Rust runtime ignores `SIGPIPE` on startup, which is very
-      // reasonable and probably means the standard library, tokio, etc. don't
-      // bother writing `MSG_NOSIGNAL` everywhere. Let's not mess with that.
-      libc::sigdelset(sigs, libc::SIGPIPE);
-   }
-}
-
-fn signals_all<T>(from_sigset: fn(&mut libc::sigset_t) -> T) -> T {
-   let mut sigs = MaybeUninit::uninit();
-   from_sigset(unsafe {
-      fill_all(sigs.as_mut_ptr());
-      sigs.assume_init_mut()
-   })
+macro_rules! libc_try_fd {
+   ($f:ident, $($xs:expr),*$(,)?) => {
+      Fd::new_unchecked(libc_try!($f, $($xs),*))
+   }
 }
 
-fn signals_deadly<T>(from_sigset: fn(&mut libc::sigset_t) -> T) -> T {
+// Linux kernel bug 9039 means that we not only can't just do the "obvious"
+// thing of using `sigwaitinfo` and calling it a day, but also have to handle
+// `SIGINT` separately when using `signalfd`. This is the reason for pretty
+// much all of the complexity in this code.
+static SIGINT_EFD: AtomicI32 = AtomicI32::new(-1);
+
+// All of the process-wide state in here only changes when a `Signals` is
+// created or dropped, so a spinlock is plenty. We're `no_std`, so it's not like
+// there's anything better lying around anyway.
+struct Lock<T> {
+   locked: AtomicBool,
+   val: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Sync for Lock<T> {}
+
+impl<T> Lock<T> {
+   const fn new(val: T) -> Self {
+      Self { locked: AtomicBool::new(false), val: UnsafeCell::new(val) }
+   }
+
+   fn lock(&self) {
+      while self
+         .locked
+         .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+         .is_err()
+      {
+         hint::spin_loop();
+      }
+   }
+
+   fn unlock(&self) {
+      self.locked.store(false, Ordering::Release);
+   }
+
+   fn with<U>(&self, f: impl FnOnce(&mut T) -> U) -> U {
+      self.lock();
+      let x = f(unsafe { &mut *self.val.get() });
+      self.unlock();
+      x
+   }
+
+   // For signal handlers, which obviously can't take the lock. Whoever's
+   // holding it has to make sure they only run while it's safe to look.
+   #[allow(dead_code)] // Depends on the architecture
+   fn as_ptr(&self) -> *mut T {
+      self.val.get()
+   }
+}
+
+// Enough for every signal on every architecture, MIPS having the most, and
+// indexed by signal number, so 0 goes to waste.
+const NSIG: usize = 129;
+
+// Who's got a handler installed for what, and what was there before, so that
+// the last one out can put it back.
+struct Handlers {
+   refs: [u32; NSIG],
+   old: [MaybeUninit<libc::sigaction>; NSIG],
+   sigint_efd_refs: usize,
+}
+
+static HANDLERS: Lock<Handlers> =
+   Lock::new(Handlers { refs: [0; NSIG], old: [MaybeUninit::uninit(); NSIG], sigint_efd_refs: 0 });
+
+// Whatever handler was there before ours, in case it wants to hear about
+// things too. Only ever changes while ours isn't installed.
+static SIGINT_CHAIN: AtomicBool = AtomicBool::new(false);
+static SIGINT_OLD: AtomicUsize = AtomicUsize::new(libc::SIG_DFL);
+static SIGINT_OLD_SIGINFO: AtomicBool = AtomicBool::new(false);
+
+// Off by default, since a handler that was written with nobody else in mind
+// is liable to do something drastic, like exiting. The default disposition
+// never gets called either way, for much the same reason.
+pub fn chain_sigint(chain: bool) {
+   SIGINT_CHAIN.store(chain, Ordering::Relaxed);
+}
+
+// Everything the eventfd can't tell us: the siginfo, and whatever was already
+// pending by the time the `SIGINT` showed up, so that it doesn't cut in line
+// ahead of those. Always pushed before the eventfd gets written, so there's
+// something to pop for every read, unless it filled up, in which case there's
+// still the plain old `SignalInfo::sigint`.
+static SIGINT_RING: Ring<(SignalInfo, SigSet), 16> = Ring::new();
+
+fn pending() -> SigSet {
    let mut sigs = MaybeUninit::uninit();
-   from_sigset(unsafe {
-      fill_all(sigs.as_mut_ptr());
-      // The default disposition of these signals is `Ign`
-      libc::sigdelset(sigs.as_mut_ptr(), libc::SIGCHLD);
-      libc::sigdelset(sigs.as_mut_ptr(), libc::SIGURG);
-      libc::sigdelset(sigs.as_mut_ptr(), libc::SIGWINCH);
-      sigs.assume_init_mut()
+   unsafe {
+      libc::sigpending(sigs.as_mut_ptr());
+      SigSet(sigs.assume_init())
+   }
+}
+
+// The one and only handler, for the `SIGINT` eventfd, the handler backend, and
+// whoever's stuck in `sigtimedwait`. The backend gets first dibs, since
+// anything it's watching isn't blocked, which means that there's no signalfd
+// in the running. After that it's whoever's listening for the doorbell, since
+// the eventfd goes along with a signalfd that'd have been happy to take a
+// `SIGINT` of its own, while `sigtimedwait` has nothing else to go on.
+extern "C" fn handler(sig: libc::c_int, info: *mut libc::siginfo_t, ctx: *mut libc::c_void) {
+   let si = SignalInfo::from_siginfo(unsafe { &*info });
+   let delivered = handlers::deliver(sig, si);
+   if sig != libc::SIGINT {
+      return;
+   }
+   // Can only be negative if we lost a race with the last `Signals` being
+   // dropped, in which case nobody cares anymore.
+   if !delivered && !sigwait::notify(si, pending()) {
+      if let Some(efd) = Fd::new(SIGINT_EFD.load(Ordering::Relaxed)) {
+         let _ = SIGINT_RING.push((si, pending()));
+         let _ = efd.write(&1u64.to_ne_bytes());
+      }
+   }
+   if !SIGINT_CHAIN.load(Ordering::Relaxed) {
+      return;
+   }
+   type Action = extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void);
+   type Handler = extern "C" fn(libc::c_int);
+   match SIGINT_OLD.load(Ordering::Relaxed) {
+      libc::SIG_DFL | libc::SIG_IGN => (),
+      f if SIGINT_OLD_SIGINFO.load(Ordering::Relaxed) => unsafe {
+         mem::transmute::<usize, Action>(f)(sig, info, ctx)
+      },
+      f => unsafe { mem::transmute::<usize, Handler>(f)(sig) },
+   }
+}
+
+// Only has to be called with the lock held.
+fn install(handlers: &mut Handlers, sig: libc::c_int) -> Result<(), Error> {
+   let i = sig as usize;
+   if handlers.refs[i] == 0 {
+      // Somebody went out of their way to ignore it, e.g. the shell for a
+      // background job, and quietly undoing that would be rude.
+      let old = unsafe {
+         libc_try!(sigaction, sig, ptr::null(), handlers.old[i].as_mut_ptr());
+         handlers.old[i].assume_init_ref()
+      };
+      if old.sa_sigaction == libc::SIG_IGN {
+         return Err(Error::Ignored(sig));
+      }
+      if sig == libc::SIGINT {
+         SIGINT_OLD.store(old.sa_sigaction, Ordering::Relaxed);
+         SIGINT_OLD_SIGINFO.store(old.sa_flags & libc::SA_SIGINFO != 0, Ordering::Relaxed);
+      }
+      let mut act = MaybeUninit::<libc::sigaction>::uninit();
+      unsafe {
+         // Seems like `libc` doesn't expose the `sa_handler` field. Pretty
+         // sure it's unioned with `sa_sigaction` on every platform that I'm
+         // ever going to care about, but we might as well avoid a nasty
+         // surprise down the road and just use `SA_SIGINFO`. `SA_RESTART`
+         // keeps us from sprinkling `EINTR`s all over everybody else's code.
+         (*act.as_mut_ptr()).sa_sigaction = handler as *const () as usize;
+         sigemptyset(&mut (*act.as_mut_ptr()).sa_mask);
+         (*act.as_mut_ptr()).sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+         libc_try!(sigaction, sig, act.as_ptr(), ptr::null_mut());
+      }
+   }
+   handlers.refs[i] += 1;
+   Ok(())
+}
+
+fn uninstall(handlers: &mut Handlers, sig: libc::c_int) {
+   let i = sig as usize;
+   handlers.refs[i] -= 1;
+   if handlers.refs[i] == 0 {
+      unsafe { libc::sigaction(sig, handlers.old[i].as_ptr(), ptr::null_mut()) };
+   }
+}
+
+// `fork` only brings along the thread that called it, so the lock gets taken
+// beforehand to make sure that nobody's halfway through with it. The child
+// then gets fresh eventfds in the exact same spots, which keeps every
+// `Signals` that it inherited working without hearing about the parent's
+// signals or vice versa.
+static ATFORK: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn atfork_prepare() {
+   HANDLERS.lock();
+}
+
+extern "C" fn atfork_parent() {
+   HANDLERS.unlock();
+}
+
+extern "C" fn atfork_child() {
+   if let Some(efd) = Fd::new(SIGINT_EFD.load(Ordering::Relaxed)) {
+      renew_efd(efd);
+   }
+   while SIGINT_RING.pop().is_some() {}
+   handlers::atfork_child();
+   sigwait::atfork_child();
+   HANDLERS.unlock();
+}
+
+fn renew_efd(efd: Fd) {
+   unsafe {
+      let new = libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK | libc::EFD_SEMAPHORE);
+      if new >= 0 {
+         libc::dup3(new, efd.get(), libc::O_CLOEXEC);
+         libc::close(new);
+      }
+   }
+}
+
+// Also only has to be called with the lock held.
+fn atfork() -> Result<(), Error> {
+   if !ATFORK.load(Ordering::Relaxed) {
+      let (prepare, parent, child) = (atfork_prepare, atfork_parent, atfork_child);
+      match unsafe { libc::pthread_atfork(Some(prepare), Some(parent), Some(child)) } {
+         0 => ATFORK.store(true, Ordering::Relaxed),
+         errno => return Err(Error::Os { call: "pthread_atfork", errno: Errno::new(errno) }),
+      }
+   }
+   Ok(())
+}
+
+fn sigint_efd() -> Result<Fd, Error> {
+   HANDLERS.with(|handlers| {
+      atfork()?;
+      if handlers.sigint_efd_refs == 0 {
+         // Anything left over from last time never made it to the eventfd.
+         while SIGINT_RING.pop().is_some() {}
+         let efd = unsafe {
+            libc_try_fd!(eventfd, 0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK | libc::EFD_SEMAPHORE)
+         };
+         SIGINT_EFD.store(efd.get(), Ordering::Relaxed);
+         if let Err(e) = install(handlers, libc::SIGINT) {
+            SIGINT_EFD.store(-1, Ordering::Relaxed);
+            let _ = efd.close();
+            return Err(e);
+         }
+      }
+      handlers.sigint_efd_refs += 1;
+      Ok(unsafe { Fd::new_unchecked(SIGINT_EFD.load(Ordering::Relaxed)) })
    })
 }
 
-fn signals_benign<T>(from_sigset: fn(&mut libc::sigset_t) -> T) -> T {
-   let mut sigs = MaybeUninit::uninit();
-   from_sigset(unsafe {
-      libc::sigemptyset(sigs.as_mut_ptr());
-      libc::sigaddset(sigs.as_mut_ptr(), libc::SIGCHLD);
-      libc::sigaddset(sigs.as_mut_ptr(), libc::SIGURG);
-      libc::sigaddset(sigs.as_mut_ptr(), libc::SIGWINCH);
-      sigs.assume_init_mut()
+fn sigint_efd_release() {
+   HANDLERS.with(|handlers| {
+      handlers.sigint_efd_refs -= 1;
+      if handlers.sigint_efd_refs == 0 {
+         uninstall(handlers, libc::SIGINT);
+         let _ = unsafe { Fd::new_unchecked(SIGINT_EFD.swap(-1, Ordering::Relaxed)) }.close();
+      }
    })
 }
 
-// The only reason pretty much anything in here can fail is if system resources
-// are exhausted, so if Rust can panic on OOM then I can too. :)))
-macro_rules! libc_unwrap {
-   ($f:path, $($xs:expr),*$(,)?) => {
-      {
-         let rc = $f($($xs),+);
-         if rc < 0 {
-            panic!("`{}` failed with error code {}", stringify!($f), *libc::__errno_location());
+// The bug is old enough that most kernels out there don't have it anymore, so
+// by default we find out whether this one does and skip the eventfd if not.
+// The other two are mostly for testing either way on the same machine.
+// Changing it only affects `Signals` that start watching `SIGINT` afterwards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SigintWorkaround {
+   Probe,
+   Always,
+   Never,
+}
+
+static SIGINT_WORKAROUND: AtomicU8 = AtomicU8::new(SigintWorkaround::Probe as u8);
+
+// 0 until somebody probes, then 1 for broken or 2 for fine.
+static SIGINT_PROBED: AtomicU8 = AtomicU8::new(0);
+
+pub fn sigint_workaround(workaround: SigintWorkaround) {
+   SIGINT_WORKAROUND.store(workaround as u8, Ordering::Relaxed);
+}
+
+fn use_sigint_efd() -> bool {
+   match SIGINT_WORKAROUND.load(Ordering::Relaxed) {
+      w if w == SigintWorkaround::Always as u8 => true,
+      w if w == SigintWorkaround::Never as u8 => false,
+      _ => match SIGINT_PROBED.load(Ordering::Relaxed) {
+         0 => {
+            let broken = !probe_sigint();
+            SIGINT_PROBED.store(2 - broken as u8, Ordering::Relaxed);
+            broken
+         },
+         probed => probed == 1,
+      },
+   }
+}
+
+// A child shares our kernel without sharing our signals, so it gets to be
+// the guinea pig: with `SIGINT` blocked and at the default disposition, it
+// either shows up on a signalfd like it's supposed to or it doesn't, and
+// either way the child isn't long for this world. Anything that goes wrong
+// along the way counts as broken, since the workaround works regardless.
+//
+// The child can only stick to async-signal-safe things. It's a bare `clone`
+// with no exit signal rather than a `fork`, which makes it a clone child as
+// far as `wait` is concerned: nobody's `waitpid(-1)` or `ChildReaper` can
+// reap it out from under us, and nobody gets a `SIGCHLD` for it either. It
+// also skips the fork handlers, which the child has no use for. With every
+// argument 0 the order doesn't matter, which is just as well, since s390
+// has its own.
+fn probe_sigint() -> bool {
+   match unsafe { libc::syscall(libc::SYS_clone, 0, 0, 0, 0, 0) as libc::pid_t } {
+      -1 => false,
+      0 => unsafe {
+         let mut sigs = MaybeUninit::uninit();
+         sigemptyset(sigs.as_mut_ptr());
+         libc::sigaddset(sigs.as_mut_ptr(), libc::SIGINT);
+         libc::signal(libc::SIGINT, libc::SIG_DFL);
+         libc::sigprocmask(libc::SIG_BLOCK, sigs.as_ptr(), ptr::null_mut());
+         let sigfd = libc::signalfd(-1, sigs.as_ptr(), libc::SFD_CLOEXEC);
+         libc::kill(libc::getpid(), libc::SIGINT);
+         let mut pollfd = libc::pollfd { fd: sigfd, events: libc::POLLIN, revents: 0 };
+         let mut info = MaybeUninit::<libc::signalfd_siginfo>::uninit();
+         let len = size_of_val(&info);
+         let ok = sigfd >= 0
+            && libc::poll(&mut pollfd, 1, 100) == 1
+            && libc::read(sigfd, info.as_mut_ptr().cast(), len) == len as isize
+            && info.assume_init().ssi_signo == libc::SIGINT as u32;
+         libc::_exit(!ok as i32)
+      },
+      pid => {
+         let mut status = 0;
+         match retry_eintr(|| match unsafe { libc::waitpid(pid, &mut status, libc::__WCLONE) } {
+            -1 => Err(Errno::last()),
+            _ => Ok(()),
+         }) {
+            Ok(()) => libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0,
+            Err(_) => false,
+         }
+      },
+   }
+}
+
+// What `install` checks for too, for when we're not going through it. A
+// blocked signal gets queued even if it's ignored, so the signalfd would be
+// perfectly happy to undo it behind everyone's back.
+fn check_ignored(sigs: &SigSet) -> Result<(), Error> {
+   for sig in sigs {
+      let mut act = MaybeUninit::<libc::sigaction>::uninit();
+      unsafe {
+         libc_try!(sigaction, sig.get(), ptr::null(), act.as_mut_ptr());
+         if act.assume_init().sa_sigaction == libc::SIG_IGN {
+            return Err(Error::Ignored(sig.get()));
          }
-         rc
       }
    }
+   Ok(())
 }
 
-macro_rules! libc_unwrap_fd {
-   ($f:path, $($xs:expr),*$(,)?) => {
-      Fd::new_unchecked(libc_unwrap!($f, $($xs),*))
+// For the child of a `fork` that's about to `exec`, so nothing that isn't
+// async-signal-safe, which includes the lock, but we're the only thread left
+// anyway. `exec` puts caught signals back to the default on its own, but
+// until then anything that lands in our handler writes to an eventfd that the
+// parent is still listening on, so those go first. The mask, on the other
+// hand, gets passed along as is.
+#[cfg(feature = "std")]
+pub(crate) fn reset_for_exec() {
+   let mut sigs = MaybeUninit::uninit();
+   unsafe {
+      let handlers = &*HANDLERS.as_ptr();
+      for sig in 1..NSIG {
+         if handlers.refs[sig] > 0 {
+            libc::signal(sig as libc::c_int, libc::SIG_DFL);
+         }
+      }
+      libc::sigemptyset(sigs.as_mut_ptr());
+      libc::pthread_sigmask(libc::SIG_SETMASK, sigs.as_ptr(), ptr::null_mut());
    }
 }
 
-// Linux kernel bug 9039 means that we not only can't just do the "obvious"
-// thing of using `sigwaitinfo` and calling it a day, but also have to handle
-// `SIGINT` separately when using `signalfd`. This is the reason for pretty
-// much all of the complexity in this code.
-static SIGINT_EFD: AtomicI32 = AtomicI32::new(-1);
+// For dying the way the signal would have killed us had we not been in the
+// way, so that the parent sees `WIFSIGNALED` and cores still get dumped. The
+// signal is raised while it's still blocked, so it's guaranteed to be pending
+// on this thread by the time it's unblocked, whoever else has it blocked or
+// not. Holding the lock keeps any `Signals` from putting our handler back in
+// the meantime.
+//
+// Signals that don't kill by default obviously can't do the job, so those get
+// the exit status that a shell would've made up instead. That goes for the
+// ones that stop us too, which would otherwise leave us hanging around until
+// somebody sent a `SIGCONT`.
+pub fn die_by(sig: Signal) -> ! {
+   if !(SigSet::terminating() | SigSet::core_dumping()).contains(sig) {
+      unsafe { libc::_exit(128 + sig.get()) };
+   }
+   let sig = sig.get();
+   HANDLERS.with(|_| {
+      let mut act = MaybeUninit::<libc::sigaction>::uninit();
+      let mut sigs = MaybeUninit::uninit();
+      unsafe {
+         (*act.as_mut_ptr()).sa_sigaction = libc::SIG_DFL;
+         sigemptyset(&mut (*act.as_mut_ptr()).sa_mask);
+         (*act.as_mut_ptr()).sa_flags = 0;
+         libc::sigaction(sig, act.as_ptr(), ptr::null_mut());
+         libc::raise(sig);
+         sigemptyset(sigs.as_mut_ptr());
+         libc::sigaddset(sigs.as_mut_ptr(), sig);
+         libc::pthread_sigmask(libc::SIG_UNBLOCK, sigs.as_ptr(), ptr::null_mut());
+      }
+   });
+   unsafe { libc::_exit(128 + sig) }
+}
 
-extern "C" fn sigint_efd_handler(_: libc::c_int, _: *mut libc::siginfo_t, _: *mut libc::c_void) {
-   let _ =
-      unsafe { Fd::new_unchecked(SIGINT_EFD.load(Ordering::Relaxed)) }.write(&1u64.to_ne_bytes());
+fn epoll(fds: &[i32]) -> Result<i32, Error> {
+   unsafe {
+      let epfd = libc_try!(epoll_create1, libc::EPOLL_CLOEXEC);
+      for &fd in fds {
+         let mut ev = libc::epoll_event { events: libc::EPOLLIN as u32, u64: fd as u64 };
+         if libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, fd, &mut ev) < 0 {
+            let e = Error::last_os("epoll_ctl");
+            let _ = Fd::new_unchecked(epfd).close();
+            return Err(e);
+         }
+      }
+      Ok(epfd)
+   }
 }
 
-fn sigint_efd() -> Fd {
-   let efd = unsafe {
-      libc_unwrap_fd!(
-         libc::eventfd,
-         0,
-         libc::EFD_CLOEXEC | libc::EFD_NONBLOCK | libc::EFD_SEMAPHORE,
-      )
+// Blocks `sigs` on the current thread and returns whichever of them weren't
+// blocked already.
+fn block(sigs: &SigSet) -> SigSet {
+   let mut old = MaybeUninit::uninit();
+   let old = unsafe {
+      libc::pthread_sigmask(libc::SIG_BLOCK, &sigs.0, old.as_mut_ptr());
+      SigSet(old.assume_init())
    };
-   match SIGINT_EFD.compare_exchange(-1, efd.get(), Ordering::Relaxed, Ordering::Relaxed) {
-      Ok(_) => {
-         let mut act = MaybeUninit::<libc::sigaction>::uninit();
-         unsafe {
-            // Seems like `libc` doesn't expose the `sa_handler` field. Pretty
-            // sure it's unioned with `sa_sigaction` on every platform that I'm
-            // ever going to care about, but we might as well avoid a nasty
-            // surprise down the road and just use `SA_SIGINFO`.
-            (*act.as_mut_ptr()).sa_sigaction = sigint_efd_handler as usize;
-            sigemptyset(&mut (*act.as_mut_ptr()).sa_mask);
-            (*act.as_mut_ptr()).sa_flags = libc::SA_SIGINFO;
-            libc_unwrap!(libc::sigaction, libc::SIGINT, act.assume_init_ref(), ptr::null_mut());
-         }
-         efd
-      },
-      Err(fd) => {
-         let _ = efd.close();
-         unsafe { Fd::new_unchecked(fd) }
-      },
+   *sigs - old
+}
+
+// signalfd is the better deal whenever it's there, since it leaves everybody
+// else's handlers alone, but some sandboxes won't allow it, in which case
+// `Auto` settles for our own handlers. Running out of fds just gets reported
+// like anything else would; `sigwait::Signals` is for doing without.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+   Auto,
+   Signalfd,
+   Handlers,
+}
+
+static NO_SIGNALFD: AtomicBool = AtomicBool::new(false);
+
+const NO_FD: Fd = unsafe { Fd::new_unchecked(-1) };
+
+fn out_of_fds(errno: Errno) -> bool {
+   errno == libc::EMFILE || errno == libc::ENFILE
+}
+
+// Either a signalfd or an eventfd for our handlers to write to, along with
+// the slot that goes with it. Seccomp tends to go with
+// `EPERM` or `ENOSYS`, but can be made to say just about anything, so `Auto`
+// doesn't look too closely. Running out of fds only means that signalfd won't
+// work right now, though, so that gets reported rather than remembered, and
+// our handlers would need an fd just the same.
+fn open(backend: Backend) -> Result<(Fd, Option<usize>), Error> {
+   let auto = backend == Backend::Auto;
+   if backend == Backend::Signalfd || auto && !NO_SIGNALFD.load(Ordering::Relaxed) {
+      let empty = SigSet::empty().0;
+      match unsafe { libc::signalfd(-1, &empty, libc::SFD_CLOEXEC | libc::SFD_NONBLOCK) } {
+         -1 if auto && !out_of_fds(Errno::last()) => NO_SIGNALFD.store(true, Ordering::Relaxed),
+         -1 => return Err(Error::last_os("signalfd")),
+         sigfd => return Ok((unsafe { Fd::new_unchecked(sigfd) }, None)),
+      }
    }
+   handlers::claim().map(|(efd, slot)| (efd, Some(slot)))
 }
 
 pub struct Signals {
    sigint_efd: i32, // Morally an `Option<NonNeg<RawFd>>` or whatever
-   sigfd: Fd,
+   sigfd: Fd,       // Or the eventfd for our handlers, if we've got a slot, or -1 for `sigwait`
+   slot: Option<usize>,
+   // Where we stand with the doorbell, if there's no fd for `SIGINT`.
+   waiter: Option<usize>,
+   // Only exists alongside the eventfd, so that there's a single fd to hand
+   // out to anyone with their own event loop.
+   epfd: i32,
+   mask: libc::sigset_t, // Minus `SIGINT` if it goes through our handler
+   // A `SIGINT` that's been read, but that has to wait its turn.
+   held: Option<(SignalInfo, SigSet)>,
+   // Whatever a preset left out for being ignored.
+   skipped: libc::sigset_t,
+   // Only the signals that weren't already blocked before we came along, so
+   // that dropping us doesn't clobber anyone else's mask.
+   unblock: libc::sigset_t,
+   thread: libc::pthread_t,
 }
 
 impl Signals {
-   fn from_sigset(sigs: &mut libc::sigset_t) -> Self {
-      unsafe {
-         let (sigint_efd, flags) = match libc::sigismember(sigs, libc::SIGINT) {
-            1 => {
-               libc::sigdelset(sigs, libc::SIGINT);
-               (sigint_efd().get(), libc::SFD_CLOEXEC | libc::SFD_NONBLOCK)
-            },
-            _ => (-1, libc::SFD_CLOEXEC),
-         };
-         libc::pthread_sigmask(libc::SIG_BLOCK, sigs, ptr::null_mut());
-         Self { sigint_efd, sigfd: libc_unwrap_fd!(libc::signalfd, -1, sigs, flags) }
-      }
+   // Anything in `sigs` that's being ignored gets an `Error::Ignored`, same as
+   // with `add`.
+   pub fn try_from_set(sigs: SigSet) -> Result<Self, Error> {
+      Self::try_with_backend(sigs, Backend::Auto)
+   }
+
+   pub fn try_with_backend(sigs: SigSet, backend: Backend) -> Result<Self, Error> {
+      let (sigfd, slot) = open(backend)?;
+      Self::with_fd(sigs, sigfd, slot)
+   }
+
+   // Starts out watching nothing and `add`s the lot, so that there's only one
+   // place that has to get the bookkeeping right.
+   pub(crate) fn with_fd(sigs: SigSet, sigfd: Fd, slot: Option<usize>) -> Result<Self, Error> {
+      let empty = SigSet::empty().0;
+      let mut s = Self {
+         sigint_efd: -1,
+         sigfd,
+         slot,
+         waiter: None,
+         epfd: -1,
+         mask: empty,
+         held: None,
+         skipped: empty,
+         unblock: empty,
+         thread: unsafe { libc::pthread_self() },
+      };
+      s.add(&sigs)?;
+      Ok(s)
+   }
+
+   pub fn try_new(sigs: &[Signal]) -> Result<Self, Error> {
+      Self::try_from_set(sigs.into())
+   }
+
+   // The presets leave out anything that's being ignored, e.g. `SIGINT` for a
+   // background job, since nobody asked for that one in particular. What got
+   // left out is in `skipped`, for anyone who'd like to complain about it.
+   // Asking by name still gets an `Error::Ignored`.
+   pub fn try_all() -> Result<Self, Error> {
+      Self::preset(SigSet::all(), Self::try_from_set)
+   }
+
+   pub fn try_deadly() -> Result<Self, Error> {
+      Self::preset(SigSet::deadly(), Self::try_from_set)
+   }
+
+   pub fn try_benign() -> Result<Self, Error> {
+      Self::preset(SigSet::benign(), Self::try_from_set)
+   }
+
+   pub(crate) fn preset(
+      sigs: SigSet,
+      new: impl FnOnce(SigSet) -> Result<Self, Error>,
+   ) -> Result<Self, Error> {
+      let skipped = sigs & SigSet::ignored();
+      let mut s = new(sigs - skipped)?;
+      s.skipped = skipped.0;
+      Ok(s)
+   }
+
+   pub fn skipped(&self) -> SigSet {
+      SigSet(self.skipped)
    }
 
    pub fn new(sigs: &[Signal]) -> Self {
-      signals_new(sigs, Self::from_sigset)
+      unwrap(Self::try_new(sigs))
+   }
+
+   pub fn with_backend(sigs: &[Signal], backend: Backend) -> Self {
+      unwrap(Self::try_with_backend(sigs.into(), backend))
    }
 
    pub fn all() -> Self {
-      signals_all(Self::from_sigset)
+      unwrap(Self::try_all())
    }
 
    pub fn deadly() -> Self {
-      signals_deadly(Self::from_sigset)
+      unwrap(Self::try_deadly())
    }
 
    pub fn benign() -> Self {
-      signals_benign(Self::from_sigset)
+      unwrap(Self::try_benign())
+   }
+
+   // For anyone who'd rather hear about it up front if some other thread can
+   // still take a signal out from under us. See `check_threads`.
+   pub fn try_checked(sigs: SigSet) -> Result<Self, Error> {
+      let s = Self::try_from_set(sigs)?;
+      s.check_threads()?;
+      Ok(s)
+   }
+
+   // The constructors only block the signals on the current thread, so any
+   // thread that was already running by then can still get them delivered
+   // the old-fashioned way. These are for finding out about that and, if need
+   // be, doing something about it.
+   //
+   // Our handlers don't need anything blocked, so there's nothing to find.
+   pub fn unblocked_threads(&self) -> Result<UnblockedThreads, Error> {
+      match self.slot {
+         Some(_) => UnblockedThreads::new(&SigSet::empty().0),
+         None => UnblockedThreads::new(&self.mask),
+      }
+   }
+
+   pub fn check_threads(&self) -> Result<(), Error> {
+      match self.unblocked_threads()?.next() {
+         Some(Ok(tid)) => Err(Error::Unblocked(tid)),
+         Some(Err(e)) => Err(e),
+         None => Ok(()),
+      }
+   }
+
+   pub fn backend(&self) -> Backend {
+      match self.slot {
+         Some(_) => Backend::Handlers,
+         None => Backend::Signalfd,
+      }
+   }
+
+   // Only ever behind a `sigwait::Signals`.
+   fn sigwait(&self) -> bool {
+      self.sigfd.get() < 0
+   }
+
+   pub fn set(&self) -> SigSet {
+      match self.has_sigint_handler() {
+         false => SigSet(self.mask),
+         true => SigSet(self.mask).with(Signal::INT),
+      }
+   }
+
+   fn has_sigint_handler(&self) -> bool {
+      self.sigint_efd >= 0 || self.waiter.is_some()
+   }
+
+   // The signalfd stays the same, so this works just fine while something is
+   // waiting on it, but the fd from `as_raw_fd` changes whenever `SIGINT`
+   // comes or goes.
+   //
+   // Adding from a thread other than the one that created us still blocks the
+   // new signals on the current thread, we just can't promise to unblock them
+   // afterwards.
+   //
+   // Any new signal that's being ignored gets an `Error::Ignored`, whether or
+   // not it came from a preset, and nothing gets added.
+   pub fn add(&mut self, sigs: &SigSet) -> Result<(), Error> {
+      if let Some(slot) = self.slot {
+         let sigs = *sigs - SigSet(self.mask);
+         handlers::add(slot, &sigs)?;
+         self.mask = (SigSet(self.mask) | sigs).0;
+         return Ok(());
+      }
+      check_ignored(&(*sigs - self.set()))?;
+      let mut sigint = false;
+      if sigs.contains(Signal::INT) && !self.set().contains(Signal::INT) && use_sigint_efd() {
+         self.add_sigint()?;
+         sigint = true;
+      }
+      let sigs = match self.has_sigint_handler() {
+         false => *sigs,
+         true => sigs.without(Signal::INT),
+      };
+      let mask = SigSet(self.mask) | sigs;
+      // We'd only ever throw it away, and so would every other waiter.
+      if let Some(doorbell) = sigwait::doorbell() {
+         if mask.contains(doorbell) {
+            if sigint {
+               self.remove_sigint();
+            }
+            return Err(Error::InUse(doorbell.get()));
+         }
+      }
+      // Whatever we're already holding stays held just the once.
+      let held = match self.on_thread() {
+         true => mask::hold(&(sigs - SigSet(self.unblock))),
+         false => Ok(block(&sigs)),
+      };
+      let held = match held {
+         Ok(held) => held,
+         Err(e) => {
+            if sigint {
+               self.remove_sigint();
+            }
+            return Err(e);
+         },
+      };
+      if self.sigfd.get() >= 0 && unsafe { libc::signalfd(self.sigfd.get(), &mask.0, 0) } < 0 {
+         let e = Error::last_os("signalfd");
+         match self.on_thread() {
+            true => mask::release(&held),
+            false => unsafe {
+               libc::pthread_sigmask(libc::SIG_UNBLOCK, &held.0, ptr::null_mut());
+            },
+         }
+         if sigint {
+            self.remove_sigint();
+         }
+         return Err(e);
+      }
+      if self.on_thread() {
+         self.unblock = (SigSet(self.unblock) | held).0;
+      }
+      self.mask = mask.0;
+      Ok(())
+   }
+
+   // Anything that's still pending gets delivered the old-fashioned way once
+   // it's unblocked, which for most signals means the process dies.
+   pub fn remove(&mut self, sigs: &SigSet) -> Result<(), Error> {
+      if let Some(slot) = self.slot {
+         handlers::remove(slot, &(*sigs & SigSet(self.mask)));
+         self.mask = (SigSet(self.mask) - *sigs).0;
+         return Ok(());
+      }
+      let mask = SigSet(self.mask) - *sigs;
+      if self.sigfd.get() >= 0 {
+         unsafe { libc_try!(signalfd, self.sigfd.get(), &mask.0, 0) };
+      }
+      self.mask = mask.0;
+      if sigs.contains(Signal::INT) {
+         self.remove_sigint();
+      }
+      if self.on_thread() {
+         let unblock = SigSet(self.unblock) & *sigs;
+         mask::release(&unblock);
+         self.unblock = (SigSet(self.unblock) - unblock).0;
+      }
+      Ok(())
+   }
+
+   fn add_sigint(&mut self) -> Result<(), Error> {
+      if self.sigwait() {
+         self.waiter = Some(sigwait::register()?);
+         return Ok(());
+      }
+      let sigint_efd = sigint_efd()?.get();
+      match epoll(&[sigint_efd, self.sigfd.get()]) {
+         Ok(epfd) => {
+            self.sigint_efd = sigint_efd;
+            self.epfd = epfd;
+            Ok(())
+         },
+         Err(e) => {
+            sigint_efd_release();
+            Err(e)
+         },
+      }
+   }
+
+   fn remove_sigint(&mut self) {
+      self.held = None;
+      if let Some(epfd) = Fd::new(mem::replace(&mut self.epfd, -1)) {
+         let _ = epfd.close();
+      }
+      if mem::replace(&mut self.sigint_efd, -1) >= 0 {
+         sigint_efd_release();
+      }
+      if let Some(waiter) = self.waiter.take() {
+         sigwait::unregister(waiter);
+      }
+   }
+
+   // The mask is per-thread, so if we've been sent somewhere else then
+   // there's nothing we can safely undo.
+   fn on_thread(&self) -> bool {
+      unsafe { libc::pthread_equal(self.thread, libc::pthread_self()) != 0 }
+   }
+
+   // Everything that the child of a `fork` shares with its parent and
+   // shouldn't, other than the `SIGINT` eventfd, which takes care of itself.
+   // A signalfd reads whichever process's signals happen to be reading it,
+   // but only wakes up whoever created it, and the epoll instance is shared
+   // outright. The fds stay the same, so anything holding onto them is none
+   // the wiser. Without any fds, there's just the doorbell, which has to ring
+   // on a thread that's still around.
+   pub fn reinit_after_fork(&mut self) -> Result<(), Error> {
+      // The eventfd for our handlers is taken care of, too.
+      if self.slot.is_some() {
+         return Ok(());
+      }
+      if let Some(waiter) = self.waiter {
+         sigwait::move_here(waiter);
+      }
+      if self.sigfd.get() >= 0 {
+         unsafe {
+            let sigfd = libc_try!(signalfd, -1, &self.mask, libc::SFD_CLOEXEC | libc::SFD_NONBLOCK);
+            libc::dup3(sigfd, self.sigfd.get(), libc::O_CLOEXEC);
+            libc::close(sigfd);
+            if self.epfd >= 0 {
+               let epfd = epoll(&[self.sigint_efd, self.sigfd.get()])?;
+               libc::dup3(epfd, self.epfd, libc::O_CLOEXEC);
+               libc::close(epfd);
+            }
+         }
+      }
+      // Whoever forked might not be the thread that created us, in which case
+      // that one is gone now along with anything we had to undo on it.
+      self.held = None;
+      if !self.on_thread() {
+         self.thread = unsafe { libc::pthread_self() };
+         self.unblock = SigSet::empty().0;
+      }
+      let held = mask::hold(&(SigSet(self.mask) - SigSet(self.unblock)))?;
+      self.unblock = (SigSet(self.unblock) | held).0;
+      Ok(())
+   }
+
+   // Blocks our signals on every thread in the process. Unlike the mask on the
+   // current thread, this isn't undone when we're dropped.
+   //
+   // Our handlers need the signals unblocked somewhere to run at all, so
+   // there's nothing to do for them, same as `unblocked_threads`.
+   //
+   // It's `Error::Unsupported` wherever `libc` has no `ucontext_t` for us,
+   // which these days means MIPS, SPARC, 32-bit PowerPC, and 64-bit PowerPC
+   // on musl. Nothing CI runs on, in other words.
+   pub fn block_threads(&self) -> Result<(), Error> {
+      match self.slot {
+         Some(_) => Ok(()),
+         None => threads::block(&self.mask),
+      }
    }
 }
 
+// `pthread_t` is a pointer on musl, but all we ever do with it is compare it.
+unsafe impl Send for Signals {}
+
 impl Drop for Signals {
    fn drop(&mut self) {
-      let _ = self.sigfd.close();
+      self.remove_sigint();
+      match self.slot {
+         Some(slot) => handlers::release(slot, &SigSet(self.mask)),
+         None => {
+            if let Some(sigfd) = Fd::new(self.sigfd.get()) {
+               let _ = sigfd.close();
+            }
+         },
+      }
+      if self.on_thread() {
+         mask::release(&SigSet(self.unblock));
+      }
    }
 }
 
-fn next(sigfd: Fd) -> Option<Signal> {
-   let mut info = MaybeUninit::<libc::signalfd_siginfo>::uninit();
-   match retry_eintr(|| sigfd.read(info.as_uninit_bytes_mut())).ok()? == size_of_val(&info) {
-      true => Some(unsafe { info.assume_init_ref() }.ssi_signo as Signal),
-      false => None,
+fn pop_sigint() -> (SignalInfo, SigSet) {
+   SIGINT_RING.pop().unwrap_or((SignalInfo::sigint(), SigSet::empty()))
+}
+
+fn read_sigint_efd(sigint_efd: Fd) -> Result<Option<(SignalInfo, SigSet)>, Error> {
+   match sigint_efd.read(MaybeUninit::<[u8; 8]>::uninit().as_uninit_bytes_mut()) {
+      Ok(_) => Ok(Some(pop_sigint())),
+      Err(EAGAIN) => Ok(None),
+      Err(errno) => Err(Error::Os { call: "read", errno }),
    }
 }
 
-fn next_with_sigint(sigint_efd: Fd, sigfd: Fd) -> Option<Signal> {
-   let mut pfds = MaybeUninit::<[libc::pollfd; 2]>::uninit();
-   let pfds = unsafe {
-      (*pfds.as_mut_ptr())[0].fd = sigint_efd.get();
-      (*pfds.as_mut_ptr())[0].events = libc::POLLIN;
-      (*pfds.as_mut_ptr())[1].fd = sigfd.get();
-      (*pfds.as_mut_ptr())[1].events = libc::POLLIN;
-      #[cfg(any(target_arch = "arm", target_arch = "x86", target_arch = "x86_64"))]
-      retry_eintr(|| syscall!(syscall::SYS_poll, pfds.as_mut_ptr(), 2, -1).check()).ok()?;
-      #[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
-      retry_eintr(|| syscall!(syscall::SYS_ppoll, pfds.as_mut_ptr(), 2, 0, 0, 0).check()).ok()?;
-      pfds.assume_init_ref()
-   };
-   if pfds[0].revents != 0 {
-      let _ = sigint_efd.read(MaybeUninit::<[u8; 8]>::uninit().as_uninit_bytes_mut());
-      return Some(libc::SIGINT);
+fn read_sigfd(sigfd: Fd) -> Result<Option<SignalInfo>, Error> {
+   let mut info = MaybeUninit::<SignalInfo>::uninit();
+   match sigfd.read(info.as_uninit_bytes_mut()) {
+      Ok(len) if len == size_of_val(&info) => Ok(Some(unsafe { info.assume_init() })),
+      Ok(len) => Err(Error::ShortRead(len)),
+      Err(EAGAIN) => Ok(None),
+      Err(errno) => Err(Error::Os { call: "read", errno }),
+   }
+}
+
+impl Signals {
+   // Both fds are non-blocking so that a timeout is actually a timeout, even
+   // if somebody else reads the signal out from under us in between `ppoll`
+   // and `read`. `poll` skips negative fds, so it's fine if there's no
+   // `SIGINT` eventfd.
+   fn next_info_until(
+      &mut self,
+      deadline: Option<libc::timespec>,
+   ) -> Result<Option<SignalInfo>, Error> {
+      if self.sigwait() {
+         return self.next_sigwait(deadline.as_ref());
+      }
+      loop {
+         if self.held.is_some() {
+            return self.next_info_now();
+         }
+         let mut pfds = [
+            libc::pollfd { fd: self.sigint_efd, events: libc::POLLIN, revents: 0 },
+            libc::pollfd { fd: self.sigfd.get(), events: libc::POLLIN, revents: 0 },
+         ];
+         let timeout = deadline.as_ref().map(sys::remaining);
+         let timeout = timeout.as_ref().map_or(ptr::null(), |ts| ts as *const _);
+         match unsafe { libc::ppoll(pfds.as_mut_ptr(), 2, timeout, ptr::null()) } {
+            0 => return Ok(None),
+            n if n < 0 => match Errno::last() {
+               EINTR => continue,
+               errno => return Err(Error::Os { call: "ppoll", errno }),
+            },
+            _ => (),
+         }
+         if let Some(info) = self.next_info_now()? {
+            return Ok(Some(info));
+         }
+      }
+   }
+
+   fn next_info_now(&mut self) -> Result<Option<SignalInfo>, Error> {
+      if self.sigwait() {
+         return self.next_sigwait(Some(&sigwait::NOW));
+      }
+      if let (None, Some(sigint_efd)) = (self.held, Fd::new(self.sigint_efd)) {
+         self.held = read_sigint_efd(sigint_efd)?;
+      }
+      match self.next_held()? {
+         Some(info) => Ok(Some(info)),
+         None => self.read(),
+      }
+   }
+
+   // Anything that isn't blocked on whichever thread is doing the waiting
+   // gets delivered the usual way in between waits, so if we've been sent
+   // somewhere else, the mask comes along. Whatever the old thread had
+   // blocked stays that way, since there's no undoing that from here.
+   //
+   // The doorbell only ever rings for whichever thread waited last, and is
+   // only blocked for as long as it takes to wait. Whenever it shows up
+   // outside of that, it goes to a handler that doesn't do anything. Ringing
+   // it is all it's good for, so it never comes out of here.
+   fn next_sigwait(
+      &mut self,
+      deadline: Option<&libc::timespec>,
+   ) -> Result<Option<SignalInfo>, Error> {
+      if !self.on_thread() {
+         self.thread = unsafe { libc::pthread_self() };
+         self.unblock = mask::hold(&SigSet(self.mask))?.0;
+      }
+      let mut sigs = SigSet(self.mask);
+      let mut ringing = SigSet::empty();
+      if let (Some(waiter), Some(doorbell)) = (self.waiter, sigwait::doorbell()) {
+         sigwait::move_here(waiter);
+         // Before looking at the queue, or else a ring could slip in between.
+         ringing = block(&doorbell.into());
+         sigs.insert(doorbell);
+      }
+      let info = loop {
+         if let (None, Some(_)) = (self.held, self.waiter) {
+            self.held = sigwait::pop();
+         }
+         match self.next_held() {
+            Ok(None) => (),
+            info => break info,
+         }
+         match sigwait::wait(&sigs, deadline) {
+            Ok(Some(info)) if Some(info.signal()) == sigwait::doorbell() => continue,
+            info => break info,
+         }
+      };
+      unsafe { libc::pthread_sigmask(libc::SIG_UNBLOCK, &ringing.0, ptr::null_mut()) };
+      info
+   }
+
+   pub(crate) fn read(&self) -> Result<Option<SignalInfo>, Error> {
+      match self.slot {
+         Some(slot) => handlers::read(slot, self.sigfd),
+         None => read_sigfd(self.sigfd),
+      }
+   }
+
+   // Whatever was pending before the held `SIGINT` goes first. The signalfd
+   // only gets to hand out those in the meantime, since otherwise it'd just
+   // go lowest first regardless. Realtime signals can have more than one
+   // pending, and there's no telling which came before, so only the first
+   // one gets to cut in line.
+   fn next_held(&mut self) -> Result<Option<SignalInfo>, Error> {
+      let Some((_, before)) = &mut self.held else {
+         return Ok(None);
+      };
+      let first = *before & SigSet(self.mask) & pending();
+      if !first.is_empty() {
+         let info = match self.sigfd.get() < 0 {
+            true => sigwait::wait(&first, Some(&sigwait::NOW))?,
+            false => unsafe {
+               libc_try!(signalfd, self.sigfd.get(), &first.0, 0);
+               let info = read_sigfd(self.sigfd);
+               libc_try!(signalfd, self.sigfd.get(), &self.mask, 0);
+               info?
+            },
+         };
+         // Somebody else might've beaten us to it.
+         if let Some(info) = info {
+            before.remove(info.signal());
+            return Ok(Some(info));
+         }
+      }
+      Ok(self.held.take().map(|(info, _)| info))
+   }
+
+   pub fn try_next_info(&mut self) -> Result<SignalInfo, Error> {
+      self.next_info_until(None).map(|info| info.unwrap())
+   }
+
+   // `Iterator::next` has nowhere to put an error, so it just gives up and
+   // returns `None` instead.
+   pub fn try_next(&mut self) -> Result<Signal, Error> {
+      self.try_next_info().map(|info| info.signal())
+   }
+
+   // For when the signal number alone isn't enough, e.g. to find out who sent
+   // it or how a child died.
+   pub fn infos(&mut self) -> Infos<'_> {
+      Infos(self)
+   }
+
+   // These return `Ok(None)` if nothing showed up in time.
+   pub fn next_info_timeout(&mut self, timeout: Duration) -> Result<Option<SignalInfo>, Error> {
+      self.next_info_until(Some(sys::deadline(timeout)))
+   }
+
+   pub fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Signal>, Error> {
+      Ok(self.next_info_timeout(timeout)?.map(|info| info.signal()))
+   }
+
+   #[cfg(feature = "std")]
+   pub fn next_deadline(&mut self, deadline: Instant) -> Result<Option<Signal>, Error> {
+      self.next_timeout(deadline.saturating_duration_since(Instant::now()))
+   }
+
+   // The non-blocking one, which would be `try_next` after `try_recv` if
+   // `try_` didn't already mean fallible everywhere else in here.
+   pub fn next_now(&mut self) -> Result<Option<Signal>, Error> {
+      Ok(self.next_info_now()?.map(|info| info.signal()))
+   }
+
+   // Everything that's already pending, without blocking. Meant for when the
+   // fd from `as_raw_fd` turns up readable in somebody else's event loop.
+   pub fn drain(&mut self) -> Drain<'_> {
+      Drain(self)
    }
-   debug_assert_ne!(pfds[1].revents, 0);
-   next(sigfd)
 }
 
 impl Iterator for Signals {
    type Item = Signal;
 
    fn next(&mut self) -> Option<Self::Item> {
-      if self.sigint_efd < 0 {
-         next(self.sigfd)
-      } else {
-         next_with_sigint(unsafe { Fd::new_unchecked(self.sigint_efd) }, self.sigfd)
+      self.try_next().ok()
+   }
+}
+
+pub struct Infos<'a>(&'a mut Signals);
+
+impl Iterator for Infos<'_> {
+   type Item = SignalInfo;
+
+   fn next(&mut self) -> Option<Self::Item> {
+      self.0.try_next_info().ok()
+   }
+}
+
+pub struct Drain<'a>(&'a mut Signals);
+
+impl Iterator for Drain<'_> {
+   type Item = Result<SignalInfo, Error>;
+
+   fn next(&mut self) -> Option<Self::Item> {
+      self.0.next_info_now().transpose()
+   }
+}
+
+#[cfg(feature = "std")]
+impl AsRawFd for Signals {
+   fn as_raw_fd(&self) -> RawFd {
+      match self.epfd < 0 {
+         true => self.sigfd.get(),
+         false => self.epfd,
       }
    }
 }
+
+#[cfg(feature = "std")]
+impl AsFd for Signals {
+   fn as_fd(&self) -> BorrowedFd<'_> {
+      unsafe { BorrowedFd::borrow_raw(self.as_raw_fd()) }
+   }
+}
@@ -0,0 +1,35 @@
+use std::process::Command;
+
+use super::reset_for_exec;
+
+// Anything we spawn inherits whatever we've got blocked, and most programs
+// never think to check, so they'd just sit there ignoring `SIGTERM` and
+// friends. `std` happens to empty the mask for the child on its own these
+// days, but nothing promises that it'll keep doing so, and our `SIGINT`
+// handler is on us to put back either way.
+pub trait CommandExt {
+   fn reset_signals(&mut self) -> &mut Self;
+}
+
+impl CommandExt for Command {
+   fn reset_signals(&mut self) -> &mut Self {
+      unsafe {
+         std::os::unix::process::CommandExt::pre_exec(self, || {
+            reset_for_exec();
+            Ok(())
+         })
+      }
+   }
+}
+
+#[cfg(feature = "tokio")]
+impl CommandExt for tokio::process::Command {
+   fn reset_signals(&mut self) -> &mut Self {
+      unsafe {
+         self.pre_exec(|| {
+            reset_for_exec();
+            Ok(())
+         })
+      }
+   }
+}
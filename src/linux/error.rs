@@ -0,0 +1,63 @@
+use core::fmt;
+
+use super::Errno;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+   Os { call: &'static str, errno: Errno },
+   InvalidSignal(libc::c_int),
+   InvalidPid(libc::pid_t),
+   InUse(libc::c_int),
+   RtExhausted,
+   UnknownSignal,
+   ShortRead(usize),
+   Unblocked(libc::pid_t),
+   Ignored(libc::c_int),
+   Unsupported(&'static str),
+}
+
+impl Error {
+   // Has to be called right after the failing call, before anything else gets
+   // a chance to stomp on `errno`.
+   pub(crate) fn last_os(call: &'static str) -> Self {
+      Self::Os { call, errno: Errno::last() }
+   }
+}
+
+impl fmt::Display for Error {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      match self {
+         Self::Os { call, errno } => write!(f, "`{call}` failed with {errno:?}"),
+         Self::InvalidSignal(sig) => write!(f, "invalid signal number {sig}"),
+         Self::UnknownSignal => f.write_str("unknown signal name"),
+         Self::InvalidPid(pid) => write!(f, "invalid pid {pid}"),
+         Self::InUse(sig) => write!(f, "signal {sig} is already reserved"),
+         Self::RtExhausted => f.write_str("no realtime signals left to reserve"),
+         Self::ShortRead(len) => write!(f, "short read of {len} bytes from signalfd"),
+         Self::Unblocked(tid) => write!(f, "thread {tid} doesn't have the signals blocked"),
+         Self::Ignored(sig) => write!(f, "signal {sig} is being ignored"),
+         Self::Unsupported(what) => write!(f, "{what} isn't supported here"),
+      }
+   }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+   fn from(e: Error) -> Self {
+      match e {
+         Error::Os { errno, .. } => errno.into(),
+         Error::InvalidSignal(_) | Error::UnknownSignal | Error::InvalidPid(_) => {
+            Self::new(std::io::ErrorKind::InvalidInput, e)
+         },
+         Error::ShortRead(_) => Self::new(std::io::ErrorKind::InvalidData, e),
+         Error::InUse(_) => Self::new(std::io::ErrorKind::AddrInUse, e),
+         Error::RtExhausted => Self::new(std::io::ErrorKind::OutOfMemory, e),
+         Error::Unblocked(_) | Error::Ignored(_) => Self::other(e),
+         Error::Unsupported(_) => Self::new(std::io::ErrorKind::Unsupported, e),
+      }
+   }
+}
@@ -0,0 +1,137 @@
+use core::hint;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+
+use super::ring::Ring;
+use super::sys::{AsUninitBytes, Fd, EAGAIN};
+use super::{atfork, install, renew_efd, uninstall, Error, SigSet, SignalInfo, HANDLERS};
+
+// The backend for when signalfd isn't an option: the signals stay unblocked
+// and our handler queues them up for whichever `Signals` is watching, with an
+// eventfd standing in for the signalfd. Only the one gets each signal, same as
+// it'd be with signalfd, so everyone needs their own queue, and there's only
+// so much room for those in a `static`.
+const SLOT_COUNT: usize = 8;
+
+struct Slot {
+   // One bit per signal, starting from 1.
+   mask: [AtomicU64; 2],
+   efd: AtomicI32,
+   ring: Ring<SignalInfo, 32>,
+}
+
+static SLOTS: [Slot; SLOT_COUNT] = [const {
+   Slot {
+      mask: [AtomicU64::new(0), AtomicU64::new(0)],
+      efd: AtomicI32::new(-1),
+      ring: Ring::new(),
+   }
+}; SLOT_COUNT];
+
+fn bit(sig: libc::c_int) -> (usize, u64) {
+   ((sig as usize - 1) / 64, 1 << ((sig as usize - 1) % 64))
+}
+
+// Whether anybody wanted it. Anything that doesn't fit in the queue gets
+// dropped, which is no worse than what happens to a standard signal that's
+// already pending.
+pub(crate) fn deliver(sig: libc::c_int, info: SignalInfo) -> bool {
+   let (i, bit) = bit(sig);
+   for slot in &SLOTS {
+      if slot.mask[i].load(Ordering::Relaxed) & bit == 0 {
+         continue;
+      }
+      // Can only be negative if we lost a race with that `Signals` being
+      // dropped, in which case the next one might still care.
+      if let Some(efd) = Fd::new(slot.efd.load(Ordering::Relaxed)) {
+         if slot.ring.push(info).is_ok() {
+            let _ = efd.write(&1u64.to_ne_bytes());
+         }
+         return true;
+      }
+   }
+   false
+}
+
+pub(crate) fn claim() -> Result<(Fd, usize), Error> {
+   HANDLERS.with(|_| {
+      atfork()?;
+      let Some(i) = SLOTS.iter().position(|slot| slot.efd.load(Ordering::Relaxed) < 0) else {
+         return Err(Error::Unsupported("more than 8 handler-based `Signals` at once"));
+      };
+      let efd =
+         unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK | libc::EFD_SEMAPHORE) };
+      if efd < 0 {
+         return Err(Error::last_os("eventfd"));
+      }
+      // Anything left over from last time never made it to the eventfd.
+      while SLOTS[i].ring.pop().is_some() {}
+      SLOTS[i].efd.store(efd, Ordering::Relaxed);
+      Ok((unsafe { Fd::new_unchecked(efd) }, i))
+   })
+}
+
+pub(crate) fn release(i: usize, sigs: &SigSet) {
+   remove(i, sigs);
+   HANDLERS.with(|_| {
+      let _ = unsafe { Fd::new_unchecked(SLOTS[i].efd.swap(-1, Ordering::Relaxed)) }.close();
+   })
+}
+
+// The bit goes in before the handler does, so that nothing shows up in
+// between with nowhere to go.
+pub(crate) fn add(i: usize, sigs: &SigSet) -> Result<(), Error> {
+   HANDLERS.with(|handlers| {
+      for (n, sig) in sigs.iter().enumerate() {
+         let (j, bit) = bit(sig.get());
+         SLOTS[i].mask[j].fetch_or(bit, Ordering::Relaxed);
+         if let Err(e) = install(handlers, sig.get()) {
+            SLOTS[i].mask[j].fetch_and(!bit, Ordering::Relaxed);
+            for sig in sigs.iter().take(n) {
+               let (j, bit) = self::bit(sig.get());
+               SLOTS[i].mask[j].fetch_and(!bit, Ordering::Relaxed);
+               uninstall(handlers, sig.get());
+            }
+            return Err(e);
+         }
+      }
+      Ok(())
+   })
+}
+
+pub(crate) fn remove(i: usize, sigs: &SigSet) {
+   HANDLERS.with(|handlers| {
+      for sig in sigs {
+         let (j, bit) = bit(sig.get());
+         SLOTS[i].mask[j].fetch_and(!bit, Ordering::Relaxed);
+         uninstall(handlers, sig.get());
+      }
+   })
+}
+
+// Every count on the eventfd is something that made it into the queue, but
+// not necessarily the thing at the front: with two handlers at once, the
+// second one can finish first, and then the first one is still in the middle
+// of its push on some other thread. It won't be long.
+pub(crate) fn read(i: usize, efd: Fd) -> Result<Option<SignalInfo>, Error> {
+   match efd.read(MaybeUninit::<[u8; 8]>::uninit().as_uninit_bytes_mut()) {
+      Ok(_) => loop {
+         match SLOTS[i].ring.pop() {
+            Some(info) => return Ok(Some(info)),
+            None => hint::spin_loop(),
+         }
+      },
+      Err(EAGAIN) => Ok(None),
+      Err(errno) => Err(Error::Os { call: "read", errno }),
+   }
+}
+
+// Called with the lock held, from the child's fork handler.
+pub(crate) fn atfork_child() {
+   for slot in &SLOTS {
+      if let Some(efd) = Fd::new(slot.efd.load(Ordering::Relaxed)) {
+         renew_efd(efd);
+         while slot.ring.pop().is_some() {}
+      }
+   }
+}
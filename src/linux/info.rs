@@ -0,0 +1,164 @@
+use core::fmt;
+use core::mem;
+
+use super::send::Sigval;
+use super::Signal;
+
+const SI_QUEUE: i32 = -1; // Not in `libc` either
+const SI_SIGIO: i32 = -5; // Or these
+#[cfg(not(any(target_arch = "mips", target_arch = "mips64")))]
+const SI_TIMER: i32 = -2;
+#[cfg(any(target_arch = "mips", target_arch = "mips64"))]
+const SI_TIMER: i32 = -3;
+
+// Just a `signalfd_siginfo` with a nicer face. Which fields are meaningful
+// depends on the signal and `si_code`, so see `sigaction(2)` for the gory
+// details; everything else is zeroed by the kernel.
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+pub struct SignalInfo(libc::signalfd_siginfo);
+
+impl SignalInfo {
+   // `SIGINT` comes in through the eventfd instead of the signalfd, so all we
+   // know about it is that it happened.
+   pub(crate) fn sigint() -> Self {
+      let mut info: libc::signalfd_siginfo = unsafe { mem::zeroed() };
+      info.ssi_signo = libc::SIGINT as u32;
+      Self(info)
+   }
+
+   // The same thing signalfd would've made out of it, for anything that
+   // somebody sent along with `SIGCHLD`. Whatever else the kernel sends on its
+   // own that's got anything more to say (`SIGIO`, `SIGSYS`, timers, ...)
+   // just gets the signal and the code.
+   pub(crate) fn from_siginfo(si: &libc::siginfo_t) -> Self {
+      let mut info: libc::signalfd_siginfo = unsafe { mem::zeroed() };
+      info.ssi_signo = si.si_signo as u32;
+      info.ssi_errno = si.si_errno;
+      info.ssi_code = si.si_code;
+      unsafe {
+         // Only somebody sending it or a child changing state fills these in.
+         // For the kernel's own `SIGSEGV`s, timers and the like, the same
+         // bytes are the fault address or whatever else, which makes for
+         // some very strange pids.
+         let sent = match si.si_code {
+            SI_TIMER | SI_SIGIO => false,
+            code => code <= 0,
+         };
+         if sent || si.si_signo == libc::SIGCHLD {
+            info.ssi_pid = si.si_pid() as u32;
+            info.ssi_uid = si.si_uid();
+         }
+         if si.si_signo == libc::SIGCHLD {
+            info.ssi_status = si.si_status();
+            info.ssi_utime = si.si_utime() as u64;
+            info.ssi_stime = si.si_stime() as u64;
+         }
+         if si.si_code == SI_QUEUE {
+            let val = Sigval { ptr: si.si_value().sival_ptr };
+            info.ssi_int = val.int;
+            info.ssi_ptr = val.ptr as u64;
+         }
+      }
+      Self(info)
+   }
+
+   pub fn signal(&self) -> Signal {
+      Signal::from_raw(self.0.ssi_signo as libc::c_int)
+   }
+
+   pub fn code(&self) -> i32 {
+      self.0.ssi_code
+   }
+
+   // `SI_USER`, `SI_QUEUE`, `SI_TKILL`, etc. are all non-positive, while
+   // anything the kernel generates on its own (`SI_KERNEL`, the terminal,
+   // `CLD_EXITED`, ...) is positive.
+   pub fn is_user(&self) -> bool {
+      self.0.ssi_code <= 0
+   }
+
+   pub fn errno(&self) -> i32 {
+      self.0.ssi_errno
+   }
+
+   pub fn pid(&self) -> libc::pid_t {
+      self.0.ssi_pid as libc::pid_t
+   }
+
+   pub fn uid(&self) -> libc::uid_t {
+      self.0.ssi_uid
+   }
+
+   pub fn status(&self) -> i32 {
+      self.0.ssi_status
+   }
+
+   pub fn addr(&self) -> u64 {
+      self.0.ssi_addr
+   }
+
+   pub fn timer_id(&self) -> u32 {
+      self.0.ssi_tid
+   }
+
+   pub fn overrun(&self) -> u32 {
+      self.0.ssi_overrun
+   }
+
+   pub fn fd(&self) -> i32 {
+      self.0.ssi_fd
+   }
+
+   pub fn band(&self) -> u32 {
+      self.0.ssi_band
+   }
+
+   // Whatever `sigqueue` sent along, if that's where it came from. Realtime
+   // signals with the same number come out in the order they were sent,
+   // while different ones come out lowest first.
+   pub fn value(&self) -> Option<i32> {
+      match self.0.ssi_code == SI_QUEUE {
+         true => Some(self.0.ssi_int),
+         false => None,
+      }
+   }
+
+   pub fn int(&self) -> i32 {
+      self.0.ssi_int
+   }
+
+   pub fn ptr(&self) -> u64 {
+      self.0.ssi_ptr
+   }
+
+   pub fn utime(&self) -> u64 {
+      self.0.ssi_utime
+   }
+
+   pub fn stime(&self) -> u64 {
+      self.0.ssi_stime
+   }
+}
+
+impl fmt::Debug for SignalInfo {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      f.debug_struct("SignalInfo")
+         .field("signal", &self.signal())
+         .field("code", &self.code())
+         .field("errno", &self.errno())
+         .field("pid", &self.pid())
+         .field("uid", &self.uid())
+         .field("status", &self.status())
+         .field("addr", &self.addr())
+         .field("timer_id", &self.timer_id())
+         .field("overrun", &self.overrun())
+         .field("fd", &self.fd())
+         .field("band", &self.band())
+         .field("int", &self.int())
+         .field("ptr", &self.ptr())
+         .field("utime", &self.utime())
+         .field("stime", &self.stime())
+         .finish()
+   }
+}
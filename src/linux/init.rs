@@ -0,0 +1,111 @@
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+
+use super::CommandExt as _;
+use super::{die_by, ChildReaper, ChildStatus, Signal, Signals};
+
+// Just enough of tini to be PID 1 in a container: start the one command we're
+// actually here for, pass along whatever signals show up, reap whatever
+// zombies get reparented to us and go away with the same status as the
+// command once it's done.
+pub struct Init {
+   cmd: Command,
+   group: bool,
+   subreaper: bool,
+}
+
+impl Init {
+   pub fn new(cmd: Command) -> Self {
+      Self { cmd, group: false, subreaper: true }
+   }
+
+   // Puts the command in its own process group and signals the whole group
+   // instead of just the command itself.
+   pub fn group(mut self, group: bool) -> Self {
+      self.group = group;
+      self
+   }
+
+   // Orphans only get reparented to us if we're PID 1 or a subreaper, so this
+   // is for when we're not PID 1. Doesn't hurt when we are.
+   pub fn subreaper(mut self, subreaper: bool) -> Self {
+      self.subreaper = subreaper;
+      self
+   }
+
+   pub fn run(mut self) -> io::Result<ChildStatus> {
+      // Has to happen before spawning to make sure that nothing slips through
+      // the cracks in between, which leaves the child to undo it for itself.
+      // Anything we're ignoring, the command already is too.
+      let mut sigs = Signals::try_all()?;
+      self.cmd.reset_signals();
+      if self.subreaper && unsafe { libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1) } < 0 {
+         return Err(io::Error::last_os_error());
+      }
+      if self.group {
+         self.cmd.process_group(0);
+      }
+      let pid = self.cmd.spawn()?.id() as libc::pid_t;
+      let target = match self.group {
+         true => {
+            // Otherwise the command is stuck in the background the moment it
+            // touches the terminal. Not having one is fine.
+            unsafe {
+               if libc::isatty(libc::STDIN_FILENO) == 1 {
+                  libc::tcsetpgrp(libc::STDIN_FILENO, pid);
+               }
+            }
+            -pid
+         },
+         false => pid,
+      };
+      let mut reaper = ChildReaper::new();
+      loop {
+         let sig = sigs.try_next()?;
+         if sig != Signal::CHLD {
+            // It's fine if it's already gone; the `SIGCHLD` is on its way.
+            unsafe { libc::kill(target, sig.get()) };
+            continue;
+         }
+         // Everyone else is an orphan that we get to clean up after. Whatever
+         // the command leaves behind gets reparented before we hear about it
+         // exiting, so finishing the pass gets those too.
+         let mut status = None;
+         for r in reaper.reap() {
+            match r? {
+               (p, s) if p == pid => status = Some(s),
+               _ => (),
+            }
+         }
+         if let Some(status) = status {
+            return Ok(status);
+         }
+      }
+   }
+
+   // `die_by` can't actually kill PID 1, since the kernel won't let anything
+   // with the default disposition through, but it falls back on the exit
+   // status a shell would use, which is what tini does anyway. Like
+   // `CommandExt::exec`, only ever comes back to say what went wrong, and
+   // it's up to the caller what to do about it.
+   pub fn exit(self) -> io::Error {
+      match self.run() {
+         Ok(status) => exit_like(status),
+         Err(e) => e,
+      }
+   }
+}
+
+// The exit status, or the death, of the command, as our own.
+pub fn exit_like(status: ChildStatus) -> ! {
+   match (status.code(), status.signal()) {
+      (Some(code), _) => std::process::exit(code),
+      (_, Some(sig)) => die_by(sig),
+      _ => std::process::exit(1),
+   }
+}
+
+pub fn run(cmd: Command) -> io::Error {
+   Init::new(cmd).exit()
+}
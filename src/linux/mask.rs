@@ -0,0 +1,78 @@
+use core::mem::size_of;
+use core::ptr;
+
+use super::sys::Errno;
+use super::{block, Error, Lock, SigSet};
+
+// How many `Signals` on each thread are counting on a signal staying blocked
+// there, so that dropping one of two that both watch `SIGTERM` doesn't pull
+// it out from under the other. Only signals that we blocked ourselves get
+// counted. Anything that was blocked before we came along is somebody else's
+// business, and stays blocked no matter what.
+//
+// There's no `thread_local!` without std, so it's a pthread key pointing at
+// an array of counts that goes away with the thread.
+type Counts = [u32; 128];
+
+static KEY: Lock<Option<libc::pthread_key_t>> = Lock::new(None);
+
+extern "C" fn free(counts: *mut libc::c_void) {
+   unsafe { libc::free(counts) };
+}
+
+fn with_counts<T>(f: impl FnOnce(&mut Counts) -> T) -> Result<T, Error> {
+   let key = KEY.with(|key| match *key {
+      Some(k) => Ok(k),
+      None => {
+         let mut k = 0;
+         match unsafe { libc::pthread_key_create(&mut k, Some(free)) } {
+            0 => Ok(*key.insert(k)),
+            errno => Err(Error::Os { call: "pthread_key_create", errno: Errno::new(errno) }),
+         }
+      },
+   })?;
+   unsafe {
+      let mut counts = libc::pthread_getspecific(key).cast::<Counts>();
+      if counts.is_null() {
+         counts = libc::calloc(1, size_of::<Counts>()).cast();
+         if counts.is_null() {
+            return Err(Error::Os { call: "calloc", errno: Errno::new(libc::ENOMEM) });
+         }
+         libc::pthread_setspecific(key, counts.cast());
+      }
+      Ok(f(&mut *counts))
+   }
+}
+
+// Blocks `sigs` on the current thread and returns whichever of them are now
+// ours to give back.
+pub(crate) fn hold(sigs: &SigSet) -> Result<SigSet, Error> {
+   with_counts(|counts| {
+      let fresh = block(sigs);
+      sigs
+         .iter()
+         .filter(|&sig| {
+            let n = &mut counts[sig.get() as usize - 1];
+            let ours = *n > 0 || fresh.contains(sig);
+            *n += ours as u32;
+            ours
+         })
+         .collect()
+   })
+}
+
+// Only for what `hold` handed out, on the same thread.
+pub(crate) fn release(sigs: &SigSet) {
+   let _ = with_counts(|counts| {
+      let done = sigs
+         .iter()
+         .filter(|&sig| {
+            let n = &mut counts[sig.get() as usize - 1];
+            let last = *n == 1;
+            *n = n.saturating_sub(1);
+            last
+         })
+         .collect::<SigSet>();
+      unsafe { libc::pthread_sigmask(libc::SIG_UNBLOCK, &done.0, ptr::null_mut()) };
+   });
+}
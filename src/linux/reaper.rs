@@ -0,0 +1,178 @@
+use core::fmt;
+
+use super::sys::{retry_eintr, Errno};
+use super::{Error, Signal};
+
+// A raw `wait` status. Only ever an exit or a death, since we never ask about
+// stopped or continued children.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ChildStatus(libc::c_int);
+
+impl ChildStatus {
+   pub fn from_raw(status: libc::c_int) -> Self {
+      Self(status)
+   }
+
+   pub fn into_raw(self) -> libc::c_int {
+      self.0
+   }
+
+   pub fn success(self) -> bool {
+      self.code() == Some(0)
+   }
+
+   pub fn code(self) -> Option<i32> {
+      match libc::WIFEXITED(self.0) {
+         true => Some(libc::WEXITSTATUS(self.0)),
+         false => None,
+      }
+   }
+
+   // Can be `SIGKILL` or `SIGSTOP`, unlike anything that comes out of a
+   // `Signals`.
+   pub fn signal(self) -> Option<Signal> {
+      match libc::WIFSIGNALED(self.0) {
+         true => Some(Signal::from_raw(libc::WTERMSIG(self.0))),
+         false => None,
+      }
+   }
+
+   pub fn core_dumped(self) -> bool {
+      libc::WIFSIGNALED(self.0) && libc::WCOREDUMP(self.0)
+   }
+}
+
+// Same format as `std::process::ExitStatus`, more or less.
+impl fmt::Display for ChildStatus {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      match (self.code(), self.signal()) {
+         (Some(code), _) => write!(f, "exit status: {code}"),
+         (_, Some(sig)) if self.core_dumped() => write!(f, "signal: {sig} (core dumped)"),
+         (_, Some(sig)) => write!(f, "signal: {sig}"),
+         _ => write!(f, "unrecognised wait status: {:#x}", self.0),
+      }
+   }
+}
+
+impl fmt::Debug for ChildStatus {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      write!(f, "ChildStatus({self})")
+   }
+}
+
+impl From<ChildStatus> for std::process::ExitStatus {
+   fn from(status: ChildStatus) -> Self {
+      std::os::unix::process::ExitStatusExt::from_raw(status.0)
+   }
+}
+
+// Any number of children exiting at once can turn into a single `SIGCHLD`, so
+// every one of them gets reaped whenever one shows up. Reaping everything
+// means that `std::process::Child::wait` and friends end up with `ECHILD`, so
+// `tracked` only touches the pids it's been told about.
+pub struct ChildReaper {
+   pids: Option<Vec<libc::pid_t>>,
+}
+
+impl ChildReaper {
+   pub fn new() -> Self {
+      Self { pids: None }
+   }
+
+   pub fn tracked() -> Self {
+      Self { pids: Some(Vec::new()) }
+   }
+
+   // Does nothing unless we're `tracked`, since otherwise everything is fair
+   // game anyway.
+   pub fn track(&mut self, pid: libc::pid_t) {
+      if let Some(pids) = &mut self.pids {
+         pids.push(pid);
+      }
+   }
+
+   pub fn untrack(&mut self, pid: libc::pid_t) {
+      if let Some(pids) = &mut self.pids {
+         pids.retain(|&p| p != pid);
+      }
+   }
+
+   // Never blocks, so it's fine to call whenever, but the obvious time is
+   // right after a `SIGCHLD`.
+   pub fn reap(&mut self) -> Reaped<'_> {
+      Reaped { reaper: self, i: 0, done: false }
+   }
+
+   // For feeding it everything that comes out of a `Signals` and letting it
+   // pick out the `SIGCHLD`s.
+   pub fn handle(&mut self, sig: Signal) -> Reaped<'_> {
+      let done = sig != Signal::CHLD;
+      Reaped { reaper: self, i: 0, done }
+   }
+}
+
+impl Default for ChildReaper {
+   fn default() -> Self {
+      Self::new()
+   }
+}
+
+fn waitpid(pid: libc::pid_t) -> Result<Option<(libc::pid_t, ChildStatus)>, Errno> {
+   let mut status = 0;
+   retry_eintr(|| match unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) } {
+      0 => Ok(None),
+      n if n < 0 => Err(Errno::last()),
+      n => Ok(Some((n, ChildStatus(status)))),
+   })
+}
+
+pub struct Reaped<'a> {
+   reaper: &'a mut ChildReaper,
+   i: usize,
+   done: bool,
+}
+
+impl Iterator for Reaped<'_> {
+   type Item = Result<(libc::pid_t, ChildStatus), Error>;
+
+   fn next(&mut self) -> Option<Self::Item> {
+      if self.done {
+         return None;
+      }
+      let Some(pids) = &mut self.reaper.pids else {
+         return match waitpid(-1) {
+            Ok(Some(child)) => Some(Ok(child)),
+            Ok(None) => {
+               self.done = true;
+               None
+            },
+            Err(errno) => {
+               self.done = true;
+               match errno == libc::ECHILD {
+                  true => None,
+                  false => Some(Err(Error::Os { call: "waitpid", errno })),
+               }
+            },
+         };
+      };
+      while let Some(&pid) = pids.get(self.i) {
+         match waitpid(pid) {
+            Ok(Some(child)) => {
+               pids.swap_remove(self.i);
+               return Some(Ok(child));
+            },
+            Ok(None) => self.i += 1,
+            // Somebody else beat us to it.
+            Err(errno) if errno == libc::ECHILD => {
+               pids.swap_remove(self.i);
+            },
+            Err(errno) => {
+               self.i += 1;
+               return Some(Err(Error::Os { call: "waitpid", errno }));
+            },
+         }
+      }
+      self.done = true;
+      None
+   }
+}
@@ -0,0 +1,181 @@
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+// A bounded MPMC queue along the lines of Vyukov's, for getting things out of
+// signal handlers, which can't take locks and can run on any number of
+// threads at once. Every slot has a turn counter: even means it's waiting on
+// a push for that lap and odd means it's waiting on a pop. That way all of the
+// counters start at 0, so the whole thing can be a `static`.
+pub(crate) struct Ring<T, const N: usize> {
+   slots: [Slot<T>; N],
+   head: AtomicUsize,
+   tail: AtomicUsize,
+}
+
+struct Slot<T> {
+   turn: AtomicUsize,
+   val: UnsafeCell<MaybeUninit<T>>,
+}
+
+unsafe impl<T: Send, const N: usize> Sync for Ring<T, N> {}
+
+impl<T: Copy, const N: usize> Ring<T, N> {
+   pub(crate) const fn new() -> Self {
+      Self {
+         slots: [const {
+            Slot { turn: AtomicUsize::new(0), val: UnsafeCell::new(MaybeUninit::uninit()) }
+         }; N],
+         head: AtomicUsize::new(0),
+         tail: AtomicUsize::new(0),
+      }
+   }
+
+   // Hands the value back if we're full, rather than waiting on a reader that
+   // might be the very thread we interrupted.
+   pub(crate) fn push(&self, val: T) -> Result<(), T> {
+      let mut pos = self.head.load(Ordering::Relaxed);
+      loop {
+         let slot = &self.slots[pos % N];
+         let turn = 2 * (pos / N);
+         match slot.turn.load(Ordering::Acquire) {
+            t if t == turn => {
+               match self.head.compare_exchange_weak(
+                  pos,
+                  pos.wrapping_add(1),
+                  Ordering::Relaxed,
+                  Ordering::Relaxed,
+               ) {
+                  Ok(_) => {
+                     unsafe { (*slot.val.get()).write(val) };
+                     slot.turn.store(turn + 1, Ordering::Release);
+                     return Ok(());
+                  },
+                  Err(head) => pos = head,
+               }
+            },
+            t if t < turn => return Err(val),
+            _ => pos = self.head.load(Ordering::Relaxed),
+         }
+      }
+   }
+
+   pub(crate) fn pop(&self) -> Option<T> {
+      let mut pos = self.tail.load(Ordering::Relaxed);
+      loop {
+         let slot = &self.slots[pos % N];
+         let turn = 2 * (pos / N) + 1;
+         match slot.turn.load(Ordering::Acquire) {
+            t if t == turn => {
+               match self.tail.compare_exchange_weak(
+                  pos,
+                  pos.wrapping_add(1),
+                  Ordering::Relaxed,
+                  Ordering::Relaxed,
+               ) {
+                  Ok(_) => {
+                     let val = unsafe { (*slot.val.get()).assume_init() };
+                     slot.turn.store(turn + 1, Ordering::Release);
+                     return Some(val);
+                  },
+                  Err(tail) => pos = tail,
+               }
+            },
+            t if t < turn => return None,
+            _ => pos = self.tail.load(Ordering::Relaxed),
+         }
+      }
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   extern crate std;
+
+   use std::thread;
+   use std::vec::Vec;
+
+   use super::Ring;
+
+   #[test]
+   fn full_and_empty() {
+      let ring = Ring::<u32, 4>::new();
+      assert_eq!(ring.pop(), None);
+      for i in 0..4 {
+         assert_eq!(ring.push(i), Ok(()));
+      }
+      assert_eq!(ring.push(4), Err(4));
+      assert_eq!(ring.pop(), Some(0));
+      assert_eq!(ring.push(4), Ok(()));
+      assert_eq!(ring.push(5), Err(5));
+      for i in 1..5 {
+         assert_eq!(ring.pop(), Some(i));
+      }
+      assert_eq!(ring.pop(), None);
+   }
+
+   // Plenty of laps, at every offset, so the turn counters have to keep up.
+   #[test]
+   fn wraparound() {
+      let ring = Ring::<u32, 3>::new();
+      let mut next = 0;
+      for lap in 0..100 {
+         let n = lap % 4;
+         for i in 0..n {
+            assert_eq!(ring.push(next + i), Ok(()));
+         }
+         for i in 0..n {
+            assert_eq!(ring.pop(), Some(next + i));
+         }
+         assert_eq!(ring.pop(), None);
+         next += n;
+      }
+   }
+
+   // Everything makes it out exactly once, and nobody sees anyone's values
+   // out of order, no matter who's pushing and popping at the same time.
+   #[test]
+   fn concurrent() {
+      const PRODUCERS: u32 = 4;
+      const EACH: u32 = 10_000;
+      let ring = Ring::<u32, 8>::new();
+      let mut got = thread::scope(|s| {
+         for p in 0..PRODUCERS {
+            let ring = &ring;
+            s.spawn(move || {
+               for i in 0..EACH {
+                  let mut val = p * EACH + i;
+                  while let Err(v) = ring.push(val) {
+                     val = v;
+                     thread::yield_now();
+                  }
+               }
+            });
+         }
+         let consumers = (0..2)
+            .map(|_| {
+               s.spawn(|| {
+                  let mut got = Vec::new();
+                  let mut last = [None; PRODUCERS as usize];
+                  while got.len() < (PRODUCERS * EACH / 2) as usize {
+                     match ring.pop() {
+                        Some(val) => {
+                           let p = (val / EACH) as usize;
+                           assert!(last[p] < Some(val));
+                           last[p] = Some(val);
+                           got.push(val);
+                        },
+                        None => thread::yield_now(),
+                     }
+                  }
+                  got
+               })
+            })
+            .collect::<Vec<_>>();
+         consumers.into_iter().flat_map(|c| c.join().unwrap()).collect::<Vec<_>>()
+      });
+      got.sort();
+      assert_eq!(got, (0..PRODUCERS * EACH).collect::<Vec<_>>());
+      assert_eq!(ring.pop(), None);
+   }
+}
@@ -0,0 +1,129 @@
+use core::ptr;
+
+use super::sys::Fd;
+use super::{Error, Signal};
+
+// Both glibc and musl have it, `libc` just never got around to it.
+extern "C" {
+   fn sigqueue(pid: libc::pid_t, sig: libc::c_int, value: libc::sigval) -> libc::c_int;
+}
+
+// `libc` only knows about the pointer half of the union, and where the `int`
+// half ends up inside of it depends on the endianness.
+#[repr(C)]
+pub(crate) union Sigval {
+   pub(crate) int: libc::c_int,
+   pub(crate) ptr: *mut libc::c_void,
+}
+
+// `syscall` returns a `c_long` and everything else an `int`.
+fn check(call: &'static str, rc: impl Into<i64>) -> Result<(), Error> {
+   match rc.into() < 0 {
+      true => Err(Error::last_os(call)),
+      false => Ok(()),
+   }
+}
+
+fn check_pid(pid: libc::pid_t) -> Result<(), Error> {
+   match pid > 0 {
+      true => Ok(()),
+      false => Err(Error::InvalidPid(pid)),
+   }
+}
+
+// Only ever one process; `send_to_group` is for the rest of what `kill` does.
+pub fn send(pid: libc::pid_t, sig: Signal) -> Result<(), Error> {
+   check_pid(pid)?;
+   check("kill", unsafe { libc::kill(pid, sig.get()) })
+}
+
+// 0 is our own process group.
+pub fn send_to_group(pgid: libc::pid_t, sig: Signal) -> Result<(), Error> {
+   match pgid < 0 {
+      true => Err(Error::InvalidPid(pgid)),
+      false => check("killpg", unsafe { libc::killpg(pgid, sig.get()) }),
+   }
+}
+
+// For threads in our own process, by kernel thread id rather than
+// `pthread_t`.
+pub fn send_to_thread(tid: libc::pid_t, sig: Signal) -> Result<(), Error> {
+   check_pid(tid)?;
+   check("tgkill", unsafe {
+      libc::syscall(libc::SYS_tgkill, libc::getpid(), tid, sig.get())
+   })
+}
+
+// Shows up in `SignalInfo::int` on the other end. Realtime signals queue up,
+// payloads and all, while anything else gets merged with whatever's pending.
+pub fn queue(pid: libc::pid_t, sig: Signal, value: i32) -> Result<(), Error> {
+   check_pid(pid)?;
+   let mut val = Sigval { ptr: ptr::null_mut() };
+   val.int = value;
+   check("sigqueue", unsafe {
+      sigqueue(pid, sig.get(), libc::sigval { sival_ptr: val.ptr })
+   })
+}
+
+// Same thing, but for the pointer half, which shows up in `SignalInfo::ptr`.
+// Only useful to a process that shares our address space, or as a plain old
+// number.
+pub fn queue_ptr(pid: libc::pid_t, sig: Signal, value: usize) -> Result<(), Error> {
+   check_pid(pid)?;
+   check("sigqueue", unsafe {
+      sigqueue(pid, sig.get(), libc::sigval { sival_ptr: value as *mut libc::c_void })
+   })
+}
+
+// A pid that can't get recycled out from under us while we're holding onto
+// it, so that the signal is sure to go to the right process.
+pub struct PidFd(Fd);
+
+impl PidFd {
+   pub fn open(pid: libc::pid_t) -> Result<Self, Error> {
+      check_pid(pid)?;
+      let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+      check("pidfd_open", fd)?;
+      Ok(Self(unsafe { Fd::new_unchecked(fd as i32) }))
+   }
+
+   pub fn send(&self, sig: Signal) -> Result<(), Error> {
+      check("pidfd_send_signal", unsafe {
+         libc::syscall(
+            libc::SYS_pidfd_send_signal,
+            self.0.get(),
+            sig.get(),
+            ptr::null::<libc::siginfo_t>(),
+            0,
+         )
+      })
+   }
+}
+
+impl Drop for PidFd {
+   fn drop(&mut self) {
+      let _ = self.0.close();
+   }
+}
+
+#[cfg(feature = "std")]
+impl std::os::fd::AsRawFd for PidFd {
+   fn as_raw_fd(&self) -> i32 {
+      self.0.get()
+   }
+}
+
+#[cfg(feature = "std")]
+impl std::os::fd::AsFd for PidFd {
+   fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+      unsafe { std::os::fd::BorrowedFd::borrow_raw(self.0.get()) }
+   }
+}
+
+// For pidfds from somewhere else, e.g. `clone3` or a Unix socket.
+#[cfg(feature = "std")]
+impl From<std::os::fd::OwnedFd> for PidFd {
+   fn from(fd: std::os::fd::OwnedFd) -> Self {
+      Self(unsafe { Fd::new_unchecked(std::os::fd::IntoRawFd::into_raw_fd(fd)) })
+   }
+}
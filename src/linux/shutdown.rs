@@ -0,0 +1,305 @@
+use std::future;
+use std::process;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{die_by, unwrap, Error, SigSet, Signal, Signals};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Policy {
+   Graceful,
+   Force,
+   Ignore,
+}
+
+struct Config {
+   grace: Option<Duration>,
+   escalate: usize,
+   policies: Vec<(Signal, Policy)>,
+}
+
+impl Config {
+   fn new() -> Self {
+      Self { grace: None, escalate: 1, policies: Vec::new() }
+   }
+
+   fn policy(&self, sig: Signal) -> Policy {
+      self
+         .policies
+         .iter()
+         .rev()
+         .find(|&&(s, _)| s == sig)
+         .map_or(Policy::Graceful, |&(_, p)| p)
+   }
+}
+
+struct TokenState {
+   sig: Option<Signal>,
+   wakers: Vec<Waker>,
+}
+
+struct TokenInner {
+   state: Mutex<TokenState>,
+   cond: Condvar,
+}
+
+// Gets cancelled once the graceful part of the shutdown starts, and tells
+// whoever's asking which signal did it.
+#[derive(Clone)]
+pub struct ShutdownToken(Arc<TokenInner>);
+
+impl ShutdownToken {
+   fn new() -> Self {
+      Self(Arc::new(TokenInner {
+         state: Mutex::new(TokenState { sig: None, wakers: Vec::new() }),
+         cond: Condvar::new(),
+      }))
+   }
+
+   // A panic while holding the lock can't leave anything half-done in here.
+   fn lock(&self) -> MutexGuard<'_, TokenState> {
+      self.0.state.lock().unwrap_or_else(PoisonError::into_inner)
+   }
+
+   fn cancel(&self, sig: Signal) {
+      let mut state = self.lock();
+      state.sig.get_or_insert(sig);
+      state.wakers.drain(..).for_each(Waker::wake);
+      self.0.cond.notify_all();
+   }
+
+   pub fn signal(&self) -> Option<Signal> {
+      self.lock().sig
+   }
+
+   pub fn is_cancelled(&self) -> bool {
+      self.signal().is_some()
+   }
+
+   pub fn wait(&self) -> Signal {
+      let mut state = self.lock();
+      loop {
+         match state.sig {
+            Some(sig) => return sig,
+            None => state = self.0.cond.wait(state).unwrap_or_else(PoisonError::into_inner),
+         }
+      }
+   }
+
+   pub fn wait_timeout(&self, timeout: Duration) -> Option<Signal> {
+      let state = self.lock();
+      let (state, _) = self
+         .0
+         .cond
+         .wait_timeout_while(state, timeout, |state| state.sig.is_none())
+         .unwrap_or_else(PoisonError::into_inner);
+      state.sig
+   }
+
+   pub fn poll_cancelled(&self, cx: &mut Context) -> Poll<Signal> {
+      let mut state = self.lock();
+      if let Some(sig) = state.sig {
+         return Poll::Ready(sig);
+      }
+      if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+         state.wakers.push(cx.waker().clone());
+      }
+      Poll::Pending
+   }
+
+   pub async fn cancelled(&self) -> Signal {
+      future::poll_fn(|cx| self.poll_cancelled(cx)).await
+   }
+}
+
+// What's left of a `Shutdown` once the graceful part has started. Dropping it
+// doesn't call anything off; the watchdog keeps going regardless.
+pub struct Draining {
+   sig: Signal,
+   token: ShutdownToken,
+}
+
+impl Draining {
+   pub fn signal(&self) -> Signal {
+      self.sig
+   }
+
+   pub fn token(&self) -> ShutdownToken {
+      self.token.clone()
+   }
+
+   // The same exit status that a shell would report had the signal killed us.
+   pub fn exit(self) -> ! {
+      exit(self.sig)
+   }
+
+   // Or actually let it kill us, now that we're done.
+   pub fn die(self) -> ! {
+      die_by(self.sig)
+   }
+}
+
+fn exit(sig: Signal) -> ! {
+   process::exit(128 + sig.get())
+}
+
+// Lives on its own thread so that it can still pull the plug when whatever is
+// supposed to be draining (a runtime, say) is wedged.
+fn watchdog(mut sigs: Signals, config: Config, first: Signal) {
+   let deadline = config.grace.map(|grace| Instant::now() + grace);
+   let mut left = config.escalate;
+   loop {
+      let next = match deadline {
+         Some(deadline) => sigs.next_deadline(deadline),
+         None => sigs.try_next().map(Some),
+      };
+      match next {
+         Ok(None) => exit(first),
+         Ok(Some(sig)) => match config.policy(sig) {
+            Policy::Ignore => (),
+            Policy::Force => exit(sig),
+            Policy::Graceful if left == 1 => exit(sig),
+            Policy::Graceful => left = left.saturating_sub(1),
+         },
+         // Not much else we can do, but the grace period still counts. With
+         // no grace period, nothing could ever escalate past this point, so
+         // there's no sense in waiting on a drain that might never finish.
+         Err(_) => {
+            if let Some(deadline) = deadline {
+               thread::sleep(deadline.saturating_duration_since(Instant::now()));
+            }
+            exit(first);
+         },
+      }
+   }
+}
+
+// Being stopped and continued is no reason to shut down, so job control is
+// left alone, as is anything we were told to ignore.
+fn sigset() -> SigSet {
+   SigSet::deadly() - SigSet::job_control() - SigSet::ignored()
+}
+
+// Waits for the first signal to start a graceful shutdown, then hands the
+// signals over to a watchdog that forces the issue after enough repeats or
+// once the grace period is up. By default everything is `Graceful`, a second
+// signal forces things, and there's no time limit.
+//
+// The blocking and the tokio flavour differ only in how they wait, so they're
+// both this with different `Signals`, under their own names.
+pub struct Shutdown<S = Signals> {
+   sigs: S,
+   config: Config,
+   token: ShutdownToken,
+}
+
+impl<S> Shutdown<S> {
+   pub fn grace(mut self, grace: Duration) -> Self {
+      self.config.grace = Some(grace);
+      self
+   }
+
+   // How many more `Graceful` signals it takes to force things once the
+   // first one is in, with 0 meaning never.
+   pub fn escalate_after(mut self, n: usize) -> Self {
+      self.config.escalate = n;
+      self
+   }
+
+   pub fn policy(mut self, sig: Signal, policy: Policy) -> Self {
+      self.config.policies.push((sig, policy));
+      self
+   }
+
+   pub fn token(&self) -> ShutdownToken {
+      self.token.clone()
+   }
+
+   // Whether `sig` is the one to start draining on, if it doesn't just end
+   // things right away.
+   fn starts(&self, sig: Signal) -> bool {
+      match self.config.policy(sig) {
+         Policy::Graceful => true,
+         Policy::Force => exit(sig),
+         Policy::Ignore => false,
+      }
+   }
+}
+
+impl<S> From<S> for Shutdown<S> {
+   fn from(sigs: S) -> Self {
+      Self { sigs, config: Config::new(), token: ShutdownToken::new() }
+   }
+}
+
+impl Shutdown {
+   pub fn try_new() -> Result<Self, Error> {
+      Ok(Signals::try_from_set(sigset())?.into())
+   }
+
+   pub fn new() -> Self {
+      unwrap(Self::try_new())
+   }
+
+   pub fn wait(mut self) -> Result<Draining, Error> {
+      let sig = loop {
+         let sig = self.sigs.try_next()?;
+         if self.starts(sig) {
+            break sig;
+         }
+      };
+      Ok(start(self.sigs, self.config, self.token, sig))
+   }
+}
+
+impl Default for Shutdown {
+   fn default() -> Self {
+      Self::new()
+   }
+}
+
+// The watchdog thread inherits our mask, so it's fine for it to take over.
+fn start(sigs: Signals, config: Config, token: ShutdownToken, sig: Signal) -> Draining {
+   token.cancel(sig);
+   thread::spawn(move || watchdog(sigs, config, sig));
+   Draining { sig, token }
+}
+
+#[cfg(feature = "tokio")]
+mod tokio {
+   use std::io;
+
+   use super::super::tokio::Signals;
+   use super::super::{unwrap, Error};
+   use super::{sigset, start, Draining, Shutdown};
+
+   // Same thing, except the waiting is async. The watchdog is still a plain
+   // old thread, since the whole point is that it works when nothing else does.
+   impl Shutdown<Signals> {
+      pub fn try_new() -> Result<Self, Error> {
+         Ok(Signals::try_from_set(sigset())?.into())
+      }
+
+      pub fn new() -> Self {
+         unwrap(Self::try_new())
+      }
+
+      pub async fn wait(mut self) -> io::Result<Draining> {
+         let sig = loop {
+            let sig = self.sigs.next().await?;
+            if self.starts(sig) {
+               break sig;
+            }
+         };
+         Ok(start(self.sigs.into_inner(), self.config, self.token, sig))
+      }
+   }
+
+   impl Default for Shutdown<Signals> {
+      fn default() -> Self {
+         Self::new()
+      }
+   }
+}
@@ -0,0 +1,275 @@
+use core::fmt;
+use core::str::FromStr;
+
+use super::{Error, Lock};
+
+// Everything we could ever get out of a signalfd, which rules out `SIGKILL`,
+// `SIGSTOP` and the couple of realtime signals that libc keeps for itself.
+// The first two are still around as constants for sending, but that's it.
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Signal(libc::c_int);
+
+// `SIGSTKFLT` never made it to MIPS or SPARC, and the kernel reuses its number
+// for something else there.
+#[cfg(not(any(
+   target_arch = "mips",
+   target_arch = "mips32r6",
+   target_arch = "mips64",
+   target_arch = "mips64r6",
+   target_arch = "sparc",
+   target_arch = "sparc64",
+)))]
+pub(crate) const SIGSTKFLT: libc::c_int = libc::SIGSTKFLT;
+#[cfg(any(
+   target_arch = "mips",
+   target_arch = "mips32r6",
+   target_arch = "mips64",
+   target_arch = "mips64r6",
+   target_arch = "sparc",
+   target_arch = "sparc64",
+))]
+pub(crate) const SIGSTKFLT: libc::c_int = -1;
+
+// Same wording as glibc's `strsignal`, give or take.
+const NAMES: &[(libc::c_int, &str, &str)] = &[
+   (libc::SIGHUP, "HUP", "Hangup"),
+   (libc::SIGINT, "INT", "Interrupt"),
+   (libc::SIGQUIT, "QUIT", "Quit"),
+   (libc::SIGILL, "ILL", "Illegal instruction"),
+   (libc::SIGTRAP, "TRAP", "Trace/breakpoint trap"),
+   (libc::SIGABRT, "ABRT", "Aborted"),
+   (libc::SIGBUS, "BUS", "Bus error"),
+   (libc::SIGFPE, "FPE", "Floating point exception"),
+   (libc::SIGKILL, "KILL", "Killed"),
+   (libc::SIGUSR1, "USR1", "User defined signal 1"),
+   (libc::SIGSEGV, "SEGV", "Segmentation fault"),
+   (libc::SIGUSR2, "USR2", "User defined signal 2"),
+   (libc::SIGPIPE, "PIPE", "Broken pipe"),
+   (libc::SIGALRM, "ALRM", "Alarm clock"),
+   (libc::SIGTERM, "TERM", "Terminated"),
+   (SIGSTKFLT, "STKFLT", "Stack fault"),
+   (libc::SIGCHLD, "CHLD", "Child exited"),
+   (libc::SIGCONT, "CONT", "Continued"),
+   (libc::SIGSTOP, "STOP", "Stopped (signal)"),
+   (libc::SIGTSTP, "TSTP", "Stopped"),
+   (libc::SIGTTIN, "TTIN", "Stopped (tty input)"),
+   (libc::SIGTTOU, "TTOU", "Stopped (tty output)"),
+   (libc::SIGURG, "URG", "Urgent I/O condition"),
+   (libc::SIGXCPU, "XCPU", "CPU time limit exceeded"),
+   (libc::SIGXFSZ, "XFSZ", "File size limit exceeded"),
+   (libc::SIGVTALRM, "VTALRM", "Virtual timer expired"),
+   (libc::SIGPROF, "PROF", "Profiling timer expired"),
+   (libc::SIGWINCH, "WINCH", "Window changed"),
+   (libc::SIGIO, "IO", "I/O possible"),
+   (libc::SIGPWR, "PWR", "Power failure"),
+   (libc::SIGSYS, "SYS", "Bad system call"),
+];
+
+impl Signal {
+   pub const HUP: Self = Self(libc::SIGHUP);
+   pub const INT: Self = Self(libc::SIGINT);
+   pub const QUIT: Self = Self(libc::SIGQUIT);
+   pub const ILL: Self = Self(libc::SIGILL);
+   pub const TRAP: Self = Self(libc::SIGTRAP);
+   pub const ABRT: Self = Self(libc::SIGABRT);
+   pub const BUS: Self = Self(libc::SIGBUS);
+   pub const FPE: Self = Self(libc::SIGFPE);
+   pub const USR1: Self = Self(libc::SIGUSR1);
+   pub const SEGV: Self = Self(libc::SIGSEGV);
+   pub const USR2: Self = Self(libc::SIGUSR2);
+   pub const PIPE: Self = Self(libc::SIGPIPE);
+   pub const ALRM: Self = Self(libc::SIGALRM);
+   pub const TERM: Self = Self(libc::SIGTERM);
+   pub const CHLD: Self = Self(libc::SIGCHLD);
+   pub const CONT: Self = Self(libc::SIGCONT);
+   pub const TSTP: Self = Self(libc::SIGTSTP);
+   pub const TTIN: Self = Self(libc::SIGTTIN);
+   pub const TTOU: Self = Self(libc::SIGTTOU);
+   pub const URG: Self = Self(libc::SIGURG);
+   pub const XCPU: Self = Self(libc::SIGXCPU);
+   pub const XFSZ: Self = Self(libc::SIGXFSZ);
+   pub const VTALRM: Self = Self(libc::SIGVTALRM);
+   pub const PROF: Self = Self(libc::SIGPROF);
+   pub const WINCH: Self = Self(libc::SIGWINCH);
+   pub const IO: Self = Self(libc::SIGIO);
+   pub const PWR: Self = Self(libc::SIGPWR);
+   pub const SYS: Self = Self(libc::SIGSYS);
+   pub const KILL: Self = Self(libc::SIGKILL);
+   pub const STOP: Self = Self(libc::SIGSTOP);
+
+   pub fn new(sig: libc::c_int) -> Result<Self, Error> {
+      match sig {
+         libc::SIGKILL | libc::SIGSTOP => Err(Error::InvalidSignal(sig)),
+         1..=31 => Ok(Self(sig)),
+         _ if (libc::SIGRTMIN()..=libc::SIGRTMAX()).contains(&sig) => Ok(Self(sig)),
+         _ => Err(Error::InvalidSignal(sig)),
+      }
+   }
+
+   // `SIGRTMIN+n`. `SIGRTMIN` itself isn't a constant since it depends on
+   // which libc we're stuck with.
+   pub fn rt(n: libc::c_int) -> Result<Self, Error> {
+      match n < 0 {
+         true => Err(Error::InvalidSignal(libc::SIGRTMIN().saturating_add(n))),
+         false => Self::new(libc::SIGRTMIN().saturating_add(n)),
+      }
+   }
+
+   // Only for numbers straight from the kernel.
+   pub(crate) const fn from_raw(sig: libc::c_int) -> Self {
+      Self(sig)
+   }
+
+   pub const fn get(self) -> libc::c_int {
+      self.0
+   }
+
+   pub fn is_rt(self) -> bool {
+      self.0 >= libc::SIGRTMIN()
+   }
+
+   // The `n` in `SIGRTMIN+n`.
+   pub fn rt_offset(self) -> Option<libc::c_int> {
+      match self.is_rt() {
+         true => Some(self.0 - libc::SIGRTMIN()),
+         false => None,
+      }
+   }
+
+   // How many there are to go around, once libc is done helping itself.
+   pub fn rt_count() -> libc::c_int {
+      libc::SIGRTMAX() - libc::SIGRTMIN() + 1
+   }
+
+   fn name(self) -> Option<&'static str> {
+      NAMES.iter().find(|&&(sig, ..)| sig == self.0).map(|&(_, name, _)| name)
+   }
+
+   pub fn description(self) -> &'static str {
+      match NAMES.iter().find(|&&(sig, ..)| sig == self.0) {
+         Some(&(.., desc)) => desc,
+         None if self.is_rt() => "Real-time signal",
+         None => "Unknown signal",
+      }
+   }
+}
+
+// Nothing stops anyone from using `Signal::rt` directly, but anything that
+// goes through here is at least guaranteed not to step on anything else that
+// does. 128 bits is enough for MIPS, which has the most of them by far.
+static RT_RESERVED: Lock<u128> = Lock::new(0);
+
+pub struct RtSignal(Signal);
+
+impl RtSignal {
+   // The lowest one that's still free.
+   pub fn reserve() -> Result<Self, Error> {
+      RT_RESERVED.with(|reserved| {
+         let n = (0..Signal::rt_count()).find(|&n| *reserved & 1 << n == 0);
+         let n = n.ok_or(Error::RtExhausted)?;
+         *reserved |= 1 << n;
+         Ok(Self(Signal(libc::SIGRTMIN() + n)))
+      })
+   }
+
+   // For when some other process needs to know which one it is ahead of
+   // time.
+   pub fn reserve_at(n: libc::c_int) -> Result<Self, Error> {
+      let sig = Signal::rt(n)?;
+      RT_RESERVED.with(|reserved| match *reserved & 1 << n {
+         0 => {
+            *reserved |= 1 << n;
+            Ok(Self(sig))
+         },
+         _ => Err(Error::InUse(sig.get())),
+      })
+   }
+
+   pub fn signal(&self) -> Signal {
+      self.0
+   }
+}
+
+impl Drop for RtSignal {
+   fn drop(&mut self) {
+      let n = self.0 .0 - libc::SIGRTMIN();
+      RT_RESERVED.with(|reserved| *reserved &= !(1 << n));
+   }
+}
+
+impl fmt::Debug for RtSignal {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      f.debug_tuple("RtSignal").field(&self.0).finish()
+   }
+}
+
+impl fmt::Display for Signal {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      match self.name() {
+         Some(name) => write!(f, "SIG{name}"),
+         None if self.0 == libc::SIGRTMIN() => f.write_str("SIGRTMIN"),
+         None if self.is_rt() => write!(f, "SIGRTMIN+{}", self.0 - libc::SIGRTMIN()),
+         None => write!(f, "signal {}", self.0),
+      }
+   }
+}
+
+impl fmt::Debug for Signal {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      fmt::Display::fmt(self, f)
+   }
+}
+
+// Takes whatever `kill -l` would, so "TERM", "SIGTERM", "15", "RTMIN+3" and
+// "SIGRTMAX-1" all work, in any case.
+impl FromStr for Signal {
+   type Err = Error;
+
+   fn from_str(s: &str) -> Result<Self, Error> {
+      let s = match s.get(..3) {
+         Some(sig) if sig.eq_ignore_ascii_case("SIG") => &s[3..],
+         _ => s,
+      };
+      if let Ok(sig) = s.parse() {
+         return Self::new(sig);
+      }
+      let offset = |base: libc::c_int, rest: &str, sign: u8| match rest.as_bytes().first() {
+         None => Ok(base),
+         Some(&c) if c == sign => match rest[1..].parse::<u8>() {
+            Ok(n) if sign == b'+' => Ok(base + n as libc::c_int),
+            Ok(n) => Ok(base - n as libc::c_int),
+            Err(_) => Err(Error::UnknownSignal),
+         },
+         Some(_) => Err(Error::UnknownSignal),
+      };
+      match s.get(..5) {
+         Some(rt) if rt.eq_ignore_ascii_case("RTMIN") => {
+            return Self::new(offset(libc::SIGRTMIN(), &s[5..], b'+')?)
+         },
+         Some(rt) if rt.eq_ignore_ascii_case("RTMAX") => {
+            return Self::new(offset(libc::SIGRTMAX(), &s[5..], b'-')?)
+         },
+         _ => (),
+      }
+      NAMES
+         .iter()
+         .find(|&&(sig, name, _)| sig >= 0 && name.eq_ignore_ascii_case(s))
+         .ok_or(Error::UnknownSignal)
+         .and_then(|&(sig, ..)| Self::new(sig))
+   }
+}
+
+impl TryFrom<libc::c_int> for Signal {
+   type Error = Error;
+
+   fn try_from(sig: libc::c_int) -> Result<Self, Error> {
+      Self::new(sig)
+   }
+}
+
+impl From<Signal> for libc::c_int {
+   fn from(sig: Signal) -> Self {
+      sig.0
+   }
+}
@@ -0,0 +1,295 @@
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ops::{BitAnd, BitOr, Not, Sub};
+use core::ptr;
+
+use super::signal::SIGSTKFLT;
+use super::Signal;
+
+#[derive(Clone, Copy)]
+pub struct SigSet(pub(crate) libc::sigset_t);
+
+impl SigSet {
+   pub fn empty() -> Self {
+      let mut sigs = MaybeUninit::uninit();
+      unsafe {
+         libc::sigemptyset(sigs.as_mut_ptr());
+         Self(sigs.assume_init())
+      }
+   }
+
+   // Every signal there is a `Signal` for. Not to be confused with `all`.
+   pub fn full() -> Self {
+      !Self::empty()
+   }
+
+   pub fn all() -> Self {
+      // From the `sigprocmask` man page:
+      // > If SIGBUS, SIGFPE, SIGILL, or SIGSEGV are generated while they are
+      // > blocked, the result is undefined, unless the signal was generated by
+      // > kill(2), sigqueue(3), or raise(3).
+      //
+      // The Rust runtime ignores `SIGPIPE` on startup, which is very
+      // reasonable and probably means the standard library, tokio, etc. don't
+      // bother writing `MSG_NOSIGNAL` everywhere. Let's not mess with that.
+      Self::full()
+         .without(Signal::BUS)
+         .without(Signal::FPE)
+         .without(Signal::ILL)
+         .without(Signal::SEGV)
+         .without(Signal::PIPE)
+   }
+
+   // The default disposition of everything in `benign` is `Ign`.
+   pub fn deadly() -> Self {
+      Self::all() - Self::benign()
+   }
+
+   pub fn benign() -> Self {
+      Self::from([Signal::CHLD, Signal::URG, Signal::WINCH])
+   }
+
+   // The ones whose default action is to kill the process without dumping
+   // core, realtime signals included.
+   pub fn terminating() -> Self {
+      let mut sigs = Self::from([
+         Signal::HUP,
+         Signal::INT,
+         Signal::USR1,
+         Signal::USR2,
+         Signal::PIPE,
+         Signal::ALRM,
+         Signal::TERM,
+         Signal::VTALRM,
+         Signal::PROF,
+         Signal::IO,
+         Signal::PWR,
+      ]);
+      if let Ok(sig) = Signal::new(SIGSTKFLT) {
+         sigs.insert(sig);
+      }
+      for sig in libc::SIGRTMIN()..=libc::SIGRTMAX() {
+         sigs.insert(Signal::from_raw(sig));
+      }
+      sigs
+   }
+
+   // The ones whose default action is to kill the process and dump core.
+   pub fn core_dumping() -> Self {
+      Self::from([
+         Signal::QUIT,
+         Signal::ILL,
+         Signal::TRAP,
+         Signal::ABRT,
+         Signal::BUS,
+         Signal::FPE,
+         Signal::SEGV,
+         Signal::XCPU,
+         Signal::XFSZ,
+         Signal::SYS,
+      ])
+   }
+
+   // Minus `SIGSTOP`, which can't be caught or blocked anyway.
+   pub fn job_control() -> Self {
+      Self::from([Signal::TSTP, Signal::TTIN, Signal::TTOU, Signal::CONT])
+   }
+
+   // What daemons traditionally take to mean "reread your config".
+   pub fn reload() -> Self {
+      Self::from([Signal::HUP])
+   }
+
+   // Whatever's currently set to `SIG_IGN`. Blocked signals never count as
+   // ignored, so a signalfd hears about these all the same; taking them out
+   // is how to respect whoever set things up that way, e.g. `nohup`.
+   pub fn ignored() -> Self {
+      Self::full()
+         .iter()
+         .filter(|sig| {
+            let mut act = MaybeUninit::<libc::sigaction>::uninit();
+            unsafe {
+               libc::sigaction(sig.get(), ptr::null(), act.as_mut_ptr()) == 0
+                  && act.assume_init().sa_sigaction == libc::SIG_IGN
+            }
+         })
+         .collect()
+   }
+
+   pub fn insert(&mut self, sig: Signal) {
+      unsafe { libc::sigaddset(&mut self.0, sig.get()) };
+   }
+
+   pub fn remove(&mut self, sig: Signal) {
+      unsafe { libc::sigdelset(&mut self.0, sig.get()) };
+   }
+
+   pub fn contains(&self, sig: Signal) -> bool {
+      unsafe { libc::sigismember(&self.0, sig.get()) == 1 }
+   }
+
+   pub fn with(mut self, sig: Signal) -> Self {
+      self.insert(sig);
+      self
+   }
+
+   pub fn without(mut self, sig: Signal) -> Self {
+      self.remove(sig);
+      self
+   }
+
+   pub fn is_empty(&self) -> bool {
+      self.iter().next().is_none()
+   }
+
+   pub fn len(&self) -> usize {
+      self.iter().count()
+   }
+
+   pub fn iter(&self) -> SigSetIter {
+      SigSetIter { sigs: *self, next: 1 }
+   }
+
+   pub fn union(self, other: Self) -> Self {
+      other.iter().fold(self, Self::with)
+   }
+
+   pub fn intersection(self, other: Self) -> Self {
+      self.iter().filter(|&sig| other.contains(sig)).collect()
+   }
+
+   pub fn difference(self, other: Self) -> Self {
+      other.iter().fold(self, Self::without)
+   }
+
+   pub fn complement(self) -> Self {
+      (1..=libc::SIGRTMAX())
+         .filter_map(|sig| Signal::new(sig).ok())
+         .filter(|&sig| !self.contains(sig))
+         .collect()
+   }
+}
+
+impl Default for SigSet {
+   fn default() -> Self {
+      Self::empty()
+   }
+}
+
+// `sigset_t` has room for 1024 signals on glibc and the kernel only knows
+// about 64 of them, so comparing the bytes would be asking for trouble.
+impl PartialEq for SigSet {
+   fn eq(&self, other: &Self) -> bool {
+      self.iter().eq(other.iter())
+   }
+}
+
+impl Eq for SigSet {}
+
+impl fmt::Debug for SigSet {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      f.debug_set().entries(self.iter()).finish()
+   }
+}
+
+pub struct SigSetIter {
+   sigs: SigSet,
+   next: libc::c_int,
+}
+
+impl Iterator for SigSetIter {
+   type Item = Signal;
+
+   fn next(&mut self) -> Option<Signal> {
+      while self.next <= libc::SIGRTMAX() {
+         let sig = Signal::from_raw(self.next);
+         self.next += 1;
+         // There's nothing stopping anyone from sneaking `SIGKILL` in through
+         // `libc`, but it's not going to do them any good.
+         if self.sigs.contains(sig) && Signal::new(sig.get()).is_ok() {
+            return Some(sig);
+         }
+      }
+      None
+   }
+}
+
+impl IntoIterator for SigSet {
+   type Item = Signal;
+   type IntoIter = SigSetIter;
+
+   fn into_iter(self) -> SigSetIter {
+      self.iter()
+   }
+}
+
+impl IntoIterator for &SigSet {
+   type Item = Signal;
+   type IntoIter = SigSetIter;
+
+   fn into_iter(self) -> SigSetIter {
+      self.iter()
+   }
+}
+
+impl FromIterator<Signal> for SigSet {
+   fn from_iter<I: IntoIterator<Item = Signal>>(iter: I) -> Self {
+      iter.into_iter().fold(Self::empty(), Self::with)
+   }
+}
+
+impl Extend<Signal> for SigSet {
+   fn extend<I: IntoIterator<Item = Signal>>(&mut self, iter: I) {
+      iter.into_iter().for_each(|sig| self.insert(sig));
+   }
+}
+
+impl From<Signal> for SigSet {
+   fn from(sig: Signal) -> Self {
+      Self::empty().with(sig)
+   }
+}
+
+impl<const N: usize> From<[Signal; N]> for SigSet {
+   fn from(sigs: [Signal; N]) -> Self {
+      sigs.into_iter().collect()
+   }
+}
+
+impl From<&[Signal]> for SigSet {
+   fn from(sigs: &[Signal]) -> Self {
+      sigs.iter().copied().collect()
+   }
+}
+
+impl BitOr for SigSet {
+   type Output = Self;
+
+   fn bitor(self, other: Self) -> Self {
+      self.union(other)
+   }
+}
+
+impl BitAnd for SigSet {
+   type Output = Self;
+
+   fn bitand(self, other: Self) -> Self {
+      self.intersection(other)
+   }
+}
+
+impl Sub for SigSet {
+   type Output = Self;
+
+   fn sub(self, other: Self) -> Self {
+      self.difference(other)
+   }
+}
+
+impl Not for SigSet {
+   type Output = Self;
+
+   fn not(self) -> Self {
+      self.complement()
+   }
+}
@@ -0,0 +1,264 @@
+use core::mem::{self, MaybeUninit};
+use core::ptr;
+use core::sync::atomic::{AtomicI32, Ordering};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use super::ring::Ring;
+use super::sys::{self, Errno, EAGAIN, EINTR};
+use super::{
+   atfork, install, uninstall, unwrap, Drain, Error, Infos, RtSignal, SigSet, Signal, SignalInfo,
+   UnblockedThreads, HANDLERS, NO_FD,
+};
+
+// The backend for when there's no fd to be had, or no need for one: the
+// signals stay blocked and `sigtimedwait` picks them up directly. That makes
+// it no good for an event loop, but plenty for a program that just sits there
+// until it's told to quit, and it keeps working when we're out of fds, which
+// is exactly when a shutdown signal is the one thing left that had better
+// work. Otherwise it's the same as the usual `Signals`, short of an fd to lend
+// out.
+//
+// `SIGINT` still has to go through our handler if the kernel has the bug, and
+// there's no eventfd for it to write to. Instead it queues up the siginfo same
+// as ever and rings a doorbell, a realtime signal that every waiter blocks
+// and waits on alongside everything else. The doorbell goes to each waiting
+// thread in particular, and has a handler of its own that does nothing, for
+// whenever it rings while nobody's waiting.
+pub struct Signals(super::Signals);
+
+impl Signals {
+   pub fn try_from_set(sigs: SigSet) -> Result<Self, Error> {
+      super::Signals::with_fd(sigs, NO_FD, None).map(Self)
+   }
+
+   pub fn try_new(sigs: &[Signal]) -> Result<Self, Error> {
+      Self::try_from_set(sigs.into())
+   }
+
+   pub fn try_all() -> Result<Self, Error> {
+      Self::preset(SigSet::all())
+   }
+
+   pub fn try_deadly() -> Result<Self, Error> {
+      Self::preset(SigSet::deadly())
+   }
+
+   pub fn try_benign() -> Result<Self, Error> {
+      Self::preset(SigSet::benign())
+   }
+
+   fn preset(sigs: SigSet) -> Result<Self, Error> {
+      super::Signals::preset(sigs, |sigs| super::Signals::with_fd(sigs, NO_FD, None)).map(Self)
+   }
+
+   pub fn new(sigs: &[Signal]) -> Self {
+      unwrap(Self::try_new(sigs))
+   }
+
+   pub fn all() -> Self {
+      unwrap(Self::try_all())
+   }
+
+   pub fn deadly() -> Self {
+      unwrap(Self::try_deadly())
+   }
+
+   pub fn benign() -> Self {
+      unwrap(Self::try_benign())
+   }
+
+   pub fn try_checked(sigs: SigSet) -> Result<Self, Error> {
+      let s = Self::try_from_set(sigs)?;
+      s.check_threads()?;
+      Ok(s)
+   }
+
+   pub fn unblocked_threads(&self) -> Result<UnblockedThreads, Error> {
+      self.0.unblocked_threads()
+   }
+
+   pub fn check_threads(&self) -> Result<(), Error> {
+      self.0.check_threads()
+   }
+
+   pub fn block_threads(&self) -> Result<(), Error> {
+      self.0.block_threads()
+   }
+
+   pub fn set(&self) -> SigSet {
+      self.0.set()
+   }
+
+   pub fn skipped(&self) -> SigSet {
+      self.0.skipped()
+   }
+
+   pub fn add(&mut self, sigs: &SigSet) -> Result<(), Error> {
+      self.0.add(sigs)
+   }
+
+   pub fn remove(&mut self, sigs: &SigSet) -> Result<(), Error> {
+      self.0.remove(sigs)
+   }
+
+   pub fn reinit_after_fork(&mut self) -> Result<(), Error> {
+      self.0.reinit_after_fork()
+   }
+
+   pub fn try_next_info(&mut self) -> Result<SignalInfo, Error> {
+      self.0.try_next_info()
+   }
+
+   pub fn try_next(&mut self) -> Result<Signal, Error> {
+      self.0.try_next()
+   }
+
+   pub fn infos(&mut self) -> Infos<'_> {
+      self.0.infos()
+   }
+
+   pub fn next_info_timeout(&mut self, timeout: Duration) -> Result<Option<SignalInfo>, Error> {
+      self.0.next_info_timeout(timeout)
+   }
+
+   pub fn next_timeout(&mut self, timeout: Duration) -> Result<Option<Signal>, Error> {
+      self.0.next_timeout(timeout)
+   }
+
+   #[cfg(feature = "std")]
+   pub fn next_deadline(&mut self, deadline: Instant) -> Result<Option<Signal>, Error> {
+      self.0.next_deadline(deadline)
+   }
+
+   pub fn next_now(&mut self) -> Result<Option<Signal>, Error> {
+      self.0.next_now()
+   }
+
+   pub fn drain(&mut self) -> Drain<'_> {
+      self.0.drain()
+   }
+}
+
+impl Iterator for Signals {
+   type Item = Signal;
+
+   fn next(&mut self) -> Option<Self::Item> {
+      self.0.next()
+   }
+}
+
+const WAITER_COUNT: usize = 8;
+
+// Thread ids, with 0 for nobody.
+static WAITERS: [AtomicI32; WAITER_COUNT] = [const { AtomicI32::new(0) }; WAITER_COUNT];
+
+// Reserved the first time anybody needs it and never given back, handler and
+// all, since it might still be pending on some thread or other, and whoever
+// reserved it next would be in for a surprise. It comes from the top of the
+// range, since anyone using realtime signals without reserving them is most
+// likely counting up from `SIGRTMIN`, and a `Signals` that asks for it anyway
+// gets told no.
+static DOORBELL: AtomicI32 = AtomicI32::new(0);
+
+// Any waiter can take any `SIGINT`, same as with the eventfd.
+static RING: Ring<(SignalInfo, SigSet), 16> = Ring::new();
+
+// A deadline that's long gone, for not waiting at all.
+pub(crate) const NOW: libc::timespec = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+
+pub(crate) fn doorbell() -> Option<Signal> {
+   match DOORBELL.load(Ordering::Relaxed) {
+      0 => None,
+      sig => Some(Signal::from_raw(sig)),
+   }
+}
+
+// Takes a spot for the current thread and installs the `SIGINT` handler.
+pub(crate) fn register() -> Result<usize, Error> {
+   HANDLERS.with(|handlers| {
+      atfork()?;
+      let Some(i) = WAITERS.iter().position(|tid| tid.load(Ordering::Relaxed) == 0) else {
+         return Err(Error::Unsupported(
+            "more than 8 `sigtimedwait`-based `Signals` with `SIGINT`",
+         ));
+      };
+      if doorbell().is_none() {
+         let rt = (0..Signal::rt_count())
+            .rev()
+            .find_map(|n| RtSignal::reserve_at(n).ok())
+            .ok_or(Error::RtExhausted)?;
+         // Our handler doesn't do anything with anything but `SIGINT`.
+         install(handlers, rt.signal().get())?;
+         DOORBELL.store(rt.signal().get(), Ordering::Relaxed);
+         mem::forget(rt);
+      }
+      install(handlers, libc::SIGINT)?;
+      WAITERS[i].store(unsafe { libc::gettid() }, Ordering::Relaxed);
+      Ok(i)
+   })
+}
+
+pub(crate) fn unregister(i: usize) {
+   HANDLERS.with(|handlers| {
+      WAITERS[i].store(0, Ordering::Relaxed);
+      uninstall(handlers, libc::SIGINT);
+   });
+}
+
+// For waiting on a thread other than the one that registered, which might
+// not even exist anymore after a `fork`.
+pub(crate) fn move_here(i: usize) {
+   WAITERS[i].store(unsafe { libc::gettid() }, Ordering::Relaxed);
+}
+
+// From the handler. Whether anybody was around to hear it.
+pub(crate) fn notify(info: SignalInfo, pending: SigSet) -> bool {
+   let Some(doorbell) = doorbell() else {
+      return false;
+   };
+   let mut heard = false;
+   for tid in &WAITERS {
+      let tid = tid.load(Ordering::Relaxed);
+      if tid == 0 {
+         continue;
+      }
+      if !heard {
+         let _ = RING.push((info, pending));
+         heard = true;
+      }
+      unsafe { libc::syscall(libc::SYS_tgkill, libc::getpid(), tid, doorbell.get()) };
+   }
+   heard
+}
+
+pub(crate) fn pop() -> Option<(SignalInfo, SigSet)> {
+   RING.pop()
+}
+
+// Called with the lock held, from the child's fork handler. The thread ids
+// are all the parent's, but `Signals::reinit_after_fork` takes care of that.
+pub(crate) fn atfork_child() {
+   while RING.pop().is_some() {}
+}
+
+// `None` if nothing showed up by the deadline.
+pub(crate) fn wait(
+   sigs: &SigSet,
+   deadline: Option<&libc::timespec>,
+) -> Result<Option<SignalInfo>, Error> {
+   let mut info = MaybeUninit::<libc::siginfo_t>::uninit();
+   loop {
+      let timeout = deadline.map(sys::remaining);
+      let timeout = timeout.as_ref().map_or(ptr::null(), |ts| ts as *const _);
+      match unsafe { libc::sigtimedwait(&sigs.0, info.as_mut_ptr(), timeout) } {
+         n if n < 0 => match Errno::last() {
+            EINTR => continue,
+            EAGAIN => return Ok(None),
+            errno => return Err(Error::Os { call: "sigtimedwait", errno }),
+         },
+         _ => return Ok(Some(SignalInfo::from_siginfo(unsafe { info.assume_init_ref() }))),
+      }
+   }
+}
@@ -0,0 +1,182 @@
+// Just enough of `heveanly` to get by. Its raw syscall layer only knows about a
+// handful of architectures, while going through libc works everywhere that
+// `libc` does, at the cost of a function call or two.
+use core::fmt;
+use core::mem::{size_of_val, MaybeUninit};
+use core::num::NonZeroI32;
+use core::slice;
+use core::time::Duration;
+
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct Fd(i32);
+
+impl Fd {
+   pub(crate) const fn new(fd: i32) -> Option<Self> {
+      match fd < 0 {
+         true => None,
+         false => Some(Self(fd)),
+      }
+   }
+
+   pub(crate) const unsafe fn new_unchecked(fd: i32) -> Self {
+      Self(fd)
+   }
+
+   pub(crate) fn get(self) -> i32 {
+      self.0
+   }
+
+   pub(crate) fn close(self) -> Result<(), Errno> {
+      match unsafe { libc::close(self.0) } {
+         0 => Ok(()),
+         _ => Err(Errno::last()),
+      }
+   }
+
+   pub(crate) fn read(self, buf: &mut [MaybeUninit<u8>]) -> Result<usize, Errno> {
+      match unsafe { libc::read(self.0, buf.as_mut_ptr().cast(), buf.len()) } {
+         n if n < 0 => Err(Errno::last()),
+         n => Ok(n as usize),
+      }
+   }
+
+   pub(crate) fn write(self, buf: &[u8]) -> Result<usize, Errno> {
+      match unsafe { libc::write(self.0, buf.as_ptr().cast(), buf.len()) } {
+         n if n < 0 => Err(Errno::last()),
+         n => Ok(n as usize),
+      }
+   }
+}
+
+#[cfg(feature = "std")]
+impl std::os::fd::AsRawFd for Fd {
+   fn as_raw_fd(&self) -> i32 {
+      self.0
+   }
+}
+
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Errno(NonZeroI32);
+
+pub(crate) const EAGAIN: Errno = Errno(NonZeroI32::new(libc::EAGAIN).unwrap());
+pub(crate) const EINTR: Errno = Errno(NonZeroI32::new(libc::EINTR).unwrap());
+
+impl Errno {
+   pub(crate) fn last() -> Self {
+      Self(NonZeroI32::new(unsafe { *libc::__errno_location() }).unwrap_or(NonZeroI32::MIN))
+   }
+
+   // For the handful of things that return the error instead of setting
+   // `errno`.
+   pub(crate) fn new(errno: i32) -> Self {
+      Self(NonZeroI32::new(errno).unwrap_or(NonZeroI32::MIN))
+   }
+
+   pub fn get(self) -> i32 {
+      self.0.get()
+   }
+
+   // Only the ones we're actually likely to run into. Everything else gets
+   // the number, which is what `errno(1)` is for.
+   fn name(self) -> Option<&'static str> {
+      Some(match self.get() {
+         libc::EAGAIN => "EAGAIN",
+         libc::EBADF => "EBADF",
+         libc::ECHILD => "ECHILD",
+         libc::EFAULT => "EFAULT",
+         libc::EINTR => "EINTR",
+         libc::EINVAL => "EINVAL",
+         libc::EMFILE => "EMFILE",
+         libc::ENFILE => "ENFILE",
+         libc::ENODEV => "ENODEV",
+         libc::ENOENT => "ENOENT",
+         libc::ENOMEM => "ENOMEM",
+         libc::ENOSYS => "ENOSYS",
+         libc::EPERM => "EPERM",
+         libc::ESRCH => "ESRCH",
+         _ => return None,
+      })
+   }
+}
+
+impl PartialEq<i32> for Errno {
+   fn eq(&self, other: &i32) -> bool {
+      self.get() == *other
+   }
+}
+
+impl fmt::Debug for Errno {
+   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+      match self.name() {
+         Some(name) => f.write_str(name),
+         None => f.debug_tuple("Errno").field(&self.get()).finish(),
+      }
+   }
+}
+
+#[cfg(feature = "std")]
+impl From<Errno> for std::io::Error {
+   fn from(errno: Errno) -> Self {
+      Self::from_raw_os_error(errno.get())
+   }
+}
+
+pub(crate) fn retry_eintr<T>(mut f: impl FnMut() -> Result<T, Errno>) -> Result<T, Errno> {
+   loop {
+      match f() {
+         Err(EINTR) => continue,
+         r => return r,
+      }
+   }
+}
+
+pub(crate) trait AsUninitBytes {
+   fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>];
+}
+
+impl AsUninitBytes for [u8] {
+   fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+      unsafe { slice::from_raw_parts_mut(self.as_mut_ptr().cast(), self.len()) }
+   }
+}
+
+impl<T> AsUninitBytes for MaybeUninit<T> {
+   fn as_uninit_bytes_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+      unsafe { slice::from_raw_parts_mut(self.as_mut_ptr().cast(), size_of_val(self)) }
+   }
+}
+
+pub(crate) fn now() -> libc::timespec {
+   let mut ts = MaybeUninit::uninit();
+   unsafe {
+      libc::clock_gettime(libc::CLOCK_MONOTONIC, ts.as_mut_ptr());
+      ts.assume_init()
+   }
+}
+
+// Saturates instead of overflowing, since a deadline a few decades out is as
+// good as no deadline at all.
+pub(crate) fn deadline(timeout: Duration) -> libc::timespec {
+   let now = now();
+   let nsec = now.tv_nsec + timeout.subsec_nanos() as libc::c_long;
+   let secs = timeout.as_secs().min(i32::MAX as u64) as _;
+   libc::timespec {
+      tv_sec: now.tv_sec.saturating_add(secs).saturating_add((nsec >= 1_000_000_000) as _),
+      tv_nsec: nsec % 1_000_000_000,
+   }
+}
+
+pub(crate) fn remaining(deadline: &libc::timespec) -> libc::timespec {
+   let now = now();
+   let (mut sec, mut nsec) = (deadline.tv_sec - now.tv_sec, deadline.tv_nsec - now.tv_nsec);
+   if nsec < 0 {
+      sec -= 1;
+      nsec += 1_000_000_000;
+   }
+   match sec < 0 {
+      true => libc::timespec { tv_sec: 0, tv_nsec: 0 },
+      false => libc::timespec { tv_sec: sec, tv_nsec: nsec },
+   }
+}
@@ -0,0 +1,274 @@
+use core::ffi::CStr;
+
+use super::sys::{retry_eintr, AsUninitBytes, Fd};
+use super::Error;
+
+// `pthread_sigmask` only ever affects the calling thread, and there's no
+// syscall that'll tell us about anyone else's mask, so `/proc` it is.
+pub struct UnblockedThreads {
+   dir: *mut libc::DIR,
+   mask: libc::sigset_t,
+}
+
+impl UnblockedThreads {
+   pub(crate) fn new(mask: &libc::sigset_t) -> Result<Self, Error> {
+      let dir = unsafe { libc::opendir(c"/proc/self/task".as_ptr()) };
+      match dir.is_null() {
+         true => Err(Error::last_os("opendir")),
+         false => Ok(Self { dir, mask: *mask }),
+      }
+   }
+
+   // Also hands back the thread's mask so that `block` can pick a signal that
+   // will actually get through.
+   fn next_with_mask(&mut self) -> Option<Result<(libc::pid_t, u128), Error>> {
+      unsafe {
+         loop {
+            let ent = libc::readdir(self.dir);
+            if ent.is_null() {
+               return None;
+            }
+            let name = CStr::from_ptr((*ent).d_name.as_ptr()).to_bytes();
+            let Some(tid) = parse_tid(name) else { continue };
+            let mut path = [0u8; 32];
+            path[..name.len()].copy_from_slice(name);
+            path[name.len()..name.len() + 7].copy_from_slice(b"/status");
+            let fd = libc::openat(
+               libc::dirfd(self.dir),
+               path.as_ptr().cast(),
+               libc::O_RDONLY | libc::O_CLOEXEC,
+            );
+            if fd < 0 {
+               // Threads can exit while we're looking at them, which is fine.
+               match *libc::__errno_location() {
+                  libc::ENOENT | libc::ESRCH => continue,
+                  _ => return Some(Err(Error::last_os("openat"))),
+               }
+            }
+            let fd = Fd::new_unchecked(fd);
+            let blocked = read_sigblk(fd);
+            let _ = fd.close();
+            match blocked {
+               Ok(Some(blocked)) if !covers(blocked, &self.mask) => {
+                  return Some(Ok((tid, blocked)))
+               },
+               Ok(_) => continue,
+               Err(e) => return Some(Err(e)),
+            }
+         }
+      }
+   }
+}
+
+impl Iterator for UnblockedThreads {
+   type Item = Result<libc::pid_t, Error>;
+
+   fn next(&mut self) -> Option<Self::Item> {
+      self.next_with_mask().map(|r| r.map(|(tid, _)| tid))
+   }
+}
+
+impl Drop for UnblockedThreads {
+   fn drop(&mut self) {
+      unsafe { libc::closedir(self.dir) };
+   }
+}
+
+fn parse_tid(name: &[u8]) -> Option<libc::pid_t> {
+   if name.is_empty() || name.len() > 10 {
+      return None;
+   }
+   name.iter().try_fold(0 as libc::pid_t, |tid, &c| match c {
+      b'0'..=b'9' => tid.checked_mul(10)?.checked_add((c - b'0') as libc::pid_t),
+      _ => None,
+   })
+}
+
+// The `SigBlk` line is a hex dump of the kernel's sigset, which is at most 128
+// bits (thanks, MIPS), with signal 1 in the lowest bit.
+fn read_sigblk(fd: Fd) -> Result<Option<u128>, Error> {
+   let mut buf = [0u8; 4096];
+   let mut len = 0;
+   while len < buf.len() {
+      match retry_eintr(|| fd.read(buf[len..].as_uninit_bytes_mut())) {
+         Ok(0) => break,
+         Ok(n) => len += n,
+         // The thread went away in between opening and reading.
+         Err(errno) if errno == libc::ESRCH => return Ok(None),
+         Err(errno) => return Err(Error::Os { call: "read", errno }),
+      }
+   }
+   let buf = &buf[..len];
+   let Some(start) = buf.windows(8).position(|w| w == b"SigBlk:\t") else {
+      return Ok(None);
+   };
+   Ok(Some(
+      buf[start + 8..]
+         .iter()
+         .map_while(|&c| (c as char).to_digit(16))
+         .fold(0, |m, d| m << 4 | d as u128),
+   ))
+}
+
+fn covers(blocked: u128, sigs: &libc::sigset_t) -> bool {
+   (1..=libc::SIGRTMAX())
+      .filter(|&sig| unsafe { libc::sigismember(sigs, sig) } == 1)
+      .all(|sig| blocked & 1 << (sig - 1) != 0)
+}
+
+// `libc` only has `ucontext_t` for some architectures, and the offset of
+// `uc_sigmask` isn't something to guess at.
+#[cfg(any(
+   target_arch = "aarch64",
+   target_arch = "arm",
+   target_arch = "loongarch64",
+   target_arch = "riscv64",
+   target_arch = "x86",
+   target_arch = "x86_64",
+   all(target_arch = "powerpc64", target_env = "gnu"),
+   target_arch = "s390x",
+))]
+pub(crate) use block::block;
+
+#[cfg(not(any(
+   target_arch = "aarch64",
+   target_arch = "arm",
+   target_arch = "loongarch64",
+   target_arch = "riscv64",
+   target_arch = "x86",
+   target_arch = "x86_64",
+   all(target_arch = "powerpc64", target_env = "gnu"),
+   target_arch = "s390x",
+)))]
+pub(crate) fn block(_: &libc::sigset_t) -> Result<(), Error> {
+   Err(Error::Unsupported("blocking signals on other threads"))
+}
+
+#[cfg(any(
+   target_arch = "aarch64",
+   target_arch = "arm",
+   target_arch = "loongarch64",
+   target_arch = "riscv64",
+   target_arch = "x86",
+   target_arch = "x86_64",
+   all(target_arch = "powerpc64", target_env = "gnu"),
+   target_arch = "s390x",
+))]
+mod block {
+   use core::mem::MaybeUninit;
+   use core::ptr;
+   use core::sync::atomic::{AtomicUsize, Ordering};
+
+   use super::super::sys::now;
+   use super::super::{Error, Lock};
+   use super::UnblockedThreads;
+
+   // There's no such thing as a process-wide signal mask, but a signal handler
+   // can edit the mask that gets restored when it returns. So we make every
+   // straggler run a handler that does exactly that, which is more or less how
+   // glibc implements `setuid` for multithreaded processes.
+   static BLOCK_MASK: Lock<MaybeUninit<libc::sigset_t>> = Lock::new(MaybeUninit::uninit());
+   static BLOCKED: AtomicUsize = AtomicUsize::new(0);
+
+   const SI_TKILL: libc::c_int = -6; // Not in `libc` for some reason
+
+   extern "C" fn block_handler(
+      sig: libc::c_int,
+      info: *mut libc::siginfo_t,
+      ctx: *mut libc::c_void,
+   ) {
+      unsafe {
+         let mask = (*BLOCK_MASK.as_ptr()).as_ptr();
+         let uc_sigmask = &mut (*ctx.cast::<libc::ucontext_t>()).uc_sigmask;
+         for s in 1..=libc::SIGRTMAX() {
+            if libc::sigismember(mask, s) == 1 {
+               libc::sigaddset(uc_sigmask, s);
+            }
+         }
+         if (*info).si_code == SI_TKILL && (*info).si_pid() == libc::getpid() {
+            BLOCKED.fetch_add(1, Ordering::Release);
+         } else {
+            // Somebody else's signal snuck in while our handler was installed.
+            // It's blocked here now, so it'll end up in a signalfd like it
+            // should have in the first place.
+            libc::kill(libc::getpid(), sig);
+         }
+      }
+   }
+
+   // Gives up on any one thread after a second or so. At that point it's either
+   // wedged in the kernel or something is very wrong.
+   fn block_one(tid: libc::pid_t, sig: libc::c_int) -> Result<bool, Error> {
+      let mut act = MaybeUninit::<libc::sigaction>::uninit();
+      let mut old = MaybeUninit::<libc::sigaction>::uninit();
+      unsafe {
+         (*act.as_mut_ptr()).sa_sigaction = block_handler as *const () as usize;
+         libc::sigfillset(&mut (*act.as_mut_ptr()).sa_mask);
+         (*act.as_mut_ptr()).sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+         if libc::sigaction(sig, act.as_ptr(), old.as_mut_ptr()) < 0 {
+            return Err(Error::last_os("sigaction"));
+         }
+         let n = BLOCKED.load(Ordering::Acquire);
+         if libc::syscall(libc::SYS_tgkill, libc::getpid(), tid, sig) < 0 {
+            let e = Error::last_os("tgkill");
+            libc::sigaction(sig, old.as_ptr(), ptr::null_mut());
+            return match e {
+               Error::Os { errno, .. } if errno == libc::ESRCH => Ok(true),
+               _ => Err(e),
+            };
+         }
+         let start = now();
+         while BLOCKED.load(Ordering::Acquire) == n {
+            if now().tv_sec - start.tv_sec > 1 {
+               // Putting the old disposition back now could kill the whole
+               // process once the signal finally lands, so our handler stays.
+               return Ok(false);
+            }
+            libc::sched_yield();
+         }
+         libc::sigaction(sig, old.as_ptr(), ptr::null_mut());
+      }
+      Ok(true)
+   }
+
+   pub(crate) fn block(mask: &libc::sigset_t) -> Result<(), Error> {
+      BLOCK_MASK.with(|block_mask| {
+         block_mask.write(*mask);
+         // New threads inherit the mask of whoever spawned them, so a straggler
+         // might have spawned more stragglers by the time we're done with it.
+         // glibc also blocks everything in threads that haven't quite started
+         // yet, which makes them look fine right up until they aren't, so it
+         // takes two clean passes in a row to convince us.
+         let mut clean_passes = 0;
+         for _ in 0..64 {
+            let mut threads = UnblockedThreads::new(mask)?;
+            let mut clean = true;
+            while let Some(r) = threads.next_with_mask() {
+               let (tid, blocked) = r?;
+               clean = false;
+               let sig = (1..=libc::SIGRTMAX())
+                  .find(|&sig| {
+                     (unsafe { libc::sigismember(mask, sig) }) == 1 && blocked & 1 << (sig - 1) == 0
+                  })
+                  .unwrap();
+               if !block_one(tid, sig)? {
+                  return Err(Error::Unblocked(tid));
+               }
+            }
+            match clean {
+               true => clean_passes += 1,
+               false => clean_passes = 0,
+            }
+            if clean_passes == 2 {
+               return Ok(());
+            }
+            unsafe { libc::sched_yield() };
+         }
+         match UnblockedThreads::new(mask)?.next() {
+            Some(Ok(tid)) => Err(Error::Unblocked(tid)),
+            Some(Err(e)) => Err(e),
+            None => Ok(()),
+         }
+      })
+   }
+}
@@ -1,66 +1,54 @@
+use std::future;
 use std::io;
-use std::mem::{size_of_val, MaybeUninit};
+use std::mem::MaybeUninit;
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
 
-use ::tokio::io::unix::{AsyncFd, AsyncFdReadyGuard};
+use ::tokio::io::unix::AsyncFd;
 use ::tokio::runtime;
-use ::tokio::select;
-use heveanly::errno::EAGAIN;
-use heveanly::{AsUninitBytes, Fd};
-
-use super::{signals_all, signals_benign, signals_deadly, signals_new, Signal};
-
-async fn read_sigfd(
-   mut guard: AsyncFdReadyGuard<'_, Fd>,
-   info: &mut MaybeUninit<libc::signalfd_siginfo>,
-) -> Option<io::Result<Signal>> {
-   match guard.get_inner().read(info.as_uninit_bytes_mut()) {
-      Ok(len) => Some(match len == size_of_val(info) {
-         true => Ok(unsafe { info.assume_init_ref() }.ssi_signo as Signal),
-         false => Err(io::ErrorKind::InvalidData.into()),
-      }),
-      Err(EAGAIN) => {
-         guard.clear_ready();
-         None
-      },
-      Err(ec) => Some(Err(ec.into())),
-   }
-}
+#[cfg(feature = "stream")]
+use futures_core::Stream;
+
+use super::sys::{AsUninitBytes, Fd, EAGAIN};
+use super::{pop_sigint, unwrap, Backend, Error, SigSet, Signal, SignalInfo};
 
-async fn next(sigfd: &AsyncFd<Fd>) -> io::Result<Signal> {
-   let mut info = MaybeUninit::<libc::signalfd_siginfo>::uninit();
+pub type Shutdown = super::shutdown::Shutdown<Signals>;
+
+// Whatever's behind the fd, be it a signalfd or the eventfd for our handlers,
+// gets read the same way as when blocking.
+fn poll_sigfd(
+   sigfd: &AsyncFd<Fd>,
+   sigs: &super::Signals,
+   cx: &mut Context,
+) -> Poll<io::Result<SignalInfo>> {
    loop {
-      match read_sigfd(sigfd.readable().await?, &mut info).await {
-         None => continue,
-         Some(r) => return r,
+      let mut guard = ready!(sigfd.poll_read_ready(cx))?;
+      match sigs.read()? {
+         Some(info) => return Poll::Ready(Ok(info)),
+         // Clearing readiness and polling again is what gets the waker
+         // registered, so this can't just return `Pending`.
+         None => guard.clear_ready(),
       }
    }
 }
 
-async fn next_with_sigint(sigint_efd: &AsyncFd<Fd>, sigfd: &AsyncFd<Fd>) -> io::Result<Signal> {
-   let mut info = MaybeUninit::<libc::signalfd_siginfo>::uninit();
+fn poll_sigint_efd(
+   sigint_efd: &AsyncFd<Fd>,
+   cx: &mut Context,
+) -> Poll<io::Result<(SignalInfo, SigSet)>> {
    loop {
-      select! {
-         g = sigint_efd.readable() => {
-            let mut guard = g?;
-            match guard.get_inner().read(MaybeUninit::<[u8; 8]>::uninit().as_uninit_bytes_mut()) {
-               Ok(_) => return Ok(libc::SIGINT),
-               Err(EAGAIN) => {
-                  guard.clear_ready();
-                  continue;
-               },
-               Err(e) => return Err(e.into()),
-            }
-         },
-         g = sigfd.readable() => match read_sigfd(g?, &mut info).await {
-            None => continue,
-            Some(r) => return r,
-         },
+      let mut guard = ready!(sigint_efd.poll_read_ready(cx))?;
+      match guard.get_inner().read(MaybeUninit::<[u8; 8]>::uninit().as_uninit_bytes_mut()) {
+         Ok(_) => return Poll::Ready(Ok(pop_sigint())),
+         Err(EAGAIN) => guard.clear_ready(),
+         Err(ec) => return Poll::Ready(Err(ec.into())),
       }
    }
 }
 
 enum Era {
-   Bc(super::Signals),
+   Bc,
    Ad { sigint_efd: Option<AsyncFd<Fd>>, sigfd: AsyncFd<Fd> },
 }
 
@@ -68,63 +56,168 @@ enum Era {
 // and nightly, currently at least, but I don't want to static assert it
 // since I don't care enough to break the build if it ever stops being the
 // case. If only there were a `static_warn`...
+//
+// Field order matters here: the `AsyncFd`s need to be deregistered before
+// `sigs` closes the fds out from under them.
 pub struct Signals {
    era: Era,
+   sigs: super::Signals,
 }
 
 impl Signals {
-   fn from_sigset(sigs: &mut libc::sigset_t) -> Self {
-      if runtime::Handle::try_current().is_ok() {
-         panic!("`macluhan::tokio::Signals` must be created before starting the Tokio runtime");
-      }
-      Self { era: Era::Bc(super::Signals::from_sigset(sigs)) }
+   pub fn try_from_set(sigs: SigSet) -> Result<Self, Error> {
+      super::Signals::try_from_set(sigs)?.try_into()
+   }
+
+   pub fn try_new(sigs: &[Signal]) -> Result<Self, Error> {
+      Self::try_from_set(sigs.into())
+   }
+
+   pub fn try_all() -> Result<Self, Error> {
+      super::Signals::try_all()?.try_into()
+   }
+
+   pub fn try_deadly() -> Result<Self, Error> {
+      super::Signals::try_deadly()?.try_into()
+   }
+
+   pub fn try_benign() -> Result<Self, Error> {
+      super::Signals::try_benign()?.try_into()
    }
 
    pub fn new(sigs: &[Signal]) -> Self {
-      signals_new(sigs, Self::from_sigset)
+      unwrap(Self::try_new(sigs))
    }
 
    pub fn all() -> Self {
-      signals_all(Self::from_sigset)
+      unwrap(Self::try_all())
    }
 
    pub fn deadly() -> Self {
-      signals_deadly(Self::from_sigset)
+      unwrap(Self::try_deadly())
    }
 
    pub fn benign() -> Self {
-      signals_benign(Self::from_sigset)
+      unwrap(Self::try_benign())
+   }
+
+   pub(crate) fn into_inner(self) -> super::Signals {
+      self.sigs
    }
 
-   async fn init_and_next(&mut self, sigfd: Fd, sigint_efd: i32) -> io::Result<Signal> {
-      let sigfd = AsyncFd::new(sigfd)?;
-      let (sig, sigint_efd) = if sigint_efd < 0 {
-         (next(&sigfd).await, None)
-      } else {
-         let fd = AsyncFd::new(unsafe { Fd::new_unchecked(sigint_efd) })?;
-         (next_with_sigint(&fd, &sigfd).await, Some(fd))
+   // Both fds belong to `sigs`, which outlives the `AsyncFd`s, and anything
+   // that could close or replace them sends us back to `Era::Bc` first.
+   fn register(&mut self) -> io::Result<()> {
+      let sigfd = unsafe { AsyncFd::register(self.sigs.sigfd)? };
+      let sigint_efd = match self.sigs.sigint_efd < 0 {
+         true => None,
+         false => Some(unsafe { AsyncFd::register(Fd::new_unchecked(self.sigs.sigint_efd))? }),
       };
       self.era = Era::Ad { sigint_efd, sigfd };
-      sig
+      Ok(())
    }
 
-   // Too lazy to implement `Stream`, and let's be real--the only place
-   // where this is ever going is into a `select!`.
-   pub async fn next(&mut self) -> io::Result<Signal> {
-      match &self.era {
-         Era::Bc(s) => self.init_and_next(s.sigfd, s.sigint_efd).await,
-         Era::Ad { sigint_efd: None, sigfd } => next(sigfd).await,
-         Era::Ad { sigint_efd: Some(sigint_efd), sigfd } => {
-            next_with_sigint(sigint_efd, sigfd).await
-         },
+   // Both fds get polled every time so that both wakers stay registered,
+   // unless there's already a `SIGINT` waiting its turn, in which case there's
+   // something to return either way. Same ordering as the blocking version.
+   pub fn poll_next_info(&mut self, cx: &mut Context) -> Poll<io::Result<SignalInfo>> {
+      if let Era::Bc = self.era {
+         self.register()?;
+      }
+      let Era::Ad { sigint_efd, sigfd } = &self.era else {
+         unreachable!()
+      };
+      if let (false, Some(sigint_efd)) = (self.sigs.held.is_some(), sigint_efd) {
+         if let Poll::Ready(r) = poll_sigint_efd(sigint_efd, cx) {
+            self.sigs.held = Some(r?);
+         }
+      }
+      if let Some(info) = self.sigs.next_held()? {
+         return Poll::Ready(Ok(info));
       }
+      poll_sigfd(sigfd, &self.sigs, cx)
+   }
+
+   // The runtime itself doesn't survive a `fork`, so this is only any use in
+   // a new one.
+   pub fn reinit_after_fork(&mut self) -> Result<(), Error> {
+      self.era = Era::Bc;
+      self.sigs.reinit_after_fork()
+   }
+
+   pub fn backend(&self) -> Backend {
+      self.sigs.backend()
+   }
+
+   pub fn set(&self) -> SigSet {
+      self.sigs.set()
+   }
+
+   pub fn skipped(&self) -> SigSet {
+      self.sigs.skipped()
+   }
+
+   // Same deal as `TryFrom`: inside a runtime, everybody else has to have the
+   // new signals blocked already, or else we back out. Blocking them before
+   // the runtime starts, even if we won't be watching them for a while, is
+   // the easy way to get there. Either way it's simplest to just register
+   // everything again next time we're polled.
+   pub fn add(&mut self, sigs: &SigSet) -> Result<(), Error> {
+      let new = *sigs - self.sigs.set();
+      self.era = Era::Bc;
+      self.sigs.add(&new)?;
+      if runtime::Handle::try_current().is_ok() {
+         if let Err(e) = self.sigs.check_threads() {
+            let _ = self.sigs.remove(&new);
+            return Err(e);
+         }
+      }
+      Ok(())
+   }
+
+   pub fn remove(&mut self, sigs: &SigSet) -> Result<(), Error> {
+      self.era = Era::Bc;
+      self.sigs.remove(sigs)
+   }
+
+   pub fn poll_next_signal(&mut self, cx: &mut Context) -> Poll<io::Result<Signal>> {
+      self.poll_next_info(cx).map_ok(|info| info.signal())
+   }
+
+   pub async fn next(&mut self) -> io::Result<Signal> {
+      future::poll_fn(|cx| self.poll_next_signal(cx)).await
+   }
+
+   pub async fn next_info(&mut self) -> io::Result<SignalInfo> {
+      future::poll_fn(|cx| self.poll_next_info(cx)).await
    }
 }
 
-impl Drop for Signals {
-   fn drop(&mut self) {
-      if let Era::Ad { sigfd, .. } = &self.era {
-         let _ = sigfd.get_ref().close();
+// Creating `Signals` before starting the runtime means that every worker
+// thread inherits the blocked mask. Creating it afterwards only blocks the
+// signals on whichever thread we happen to be on, and any of them that land on
+// another thread go straight to their default disposition, so we check that
+// somebody else already took care of it and bail otherwise. The usual way to
+// do that is to create a plain `macluhan::Signals` up front and convert it
+// once the runtime is up, but `Signals::block_threads` works too.
+impl TryFrom<super::Signals> for Signals {
+   type Error = Error;
+
+   fn try_from(sigs: super::Signals) -> Result<Self, Error> {
+      if runtime::Handle::try_current().is_ok() {
+         sigs.check_threads()?;
       }
+      Ok(Self { era: Era::Bc, sigs })
+   }
+}
+
+// Never ends; errors are passed along and it's up to the caller whether to
+// keep going.
+#[cfg(feature = "stream")]
+impl Stream for Signals {
+   type Item = io::Result<Signal>;
+
+   fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+      self.get_mut().poll_next_signal(cx).map(Some)
    }
 }
@@ -0,0 +1,73 @@
+mod common;
+
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::Duration;
+
+use macluhan::{
+   chain_sigint, send_to_thread, sigint_workaround, SigintWorkaround, Signal, Signals,
+};
+
+use common::in_child;
+
+static PLAIN: AtomicI32 = AtomicI32::new(0);
+static SIGINFO: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn plain(_: libc::c_int) {
+   PLAIN.fetch_add(1, Ordering::Relaxed);
+}
+
+extern "C" fn siginfo(_: libc::c_int, info: *mut libc::siginfo_t, _: *mut libc::c_void) {
+   // Has to be the real deal, or else we'd have been called the wrong way.
+   SIGINFO.store(unsafe { (*info).si_signo }, Ordering::Relaxed);
+}
+
+fn set_handler(f: usize, flags: libc::c_int) {
+   let mut act = MaybeUninit::<libc::sigaction>::zeroed();
+   unsafe {
+      (*act.as_mut_ptr()).sa_sigaction = f;
+      (*act.as_mut_ptr()).sa_flags = flags;
+      assert_eq!(libc::sigaction(libc::SIGINT, act.as_ptr(), ptr::null_mut()), 0);
+   }
+}
+
+// Whoever had `SIGINT` before us still gets to hear about it, whichever way
+// they asked to be called, and we still get ours.
+fn chained(count: &AtomicI32, expected: i32) {
+   chain_sigint(true);
+   sigint_workaround(SigintWorkaround::Always);
+   let mut sigs = Signals::new(&[Signal::INT]);
+   send_to_thread(unsafe { libc::gettid() }, Signal::INT).unwrap();
+   assert_eq!(sigs.next_timeout(Duration::from_secs(5)), Ok(Some(Signal::INT)));
+   assert_eq!(count.load(Ordering::Relaxed), expected);
+}
+
+#[test]
+fn plain_handler() {
+   in_child(|| {
+      set_handler(plain as *const () as usize, 0);
+      chained(&PLAIN, 1);
+   });
+}
+
+#[test]
+fn siginfo_handler() {
+   in_child(|| {
+      set_handler(siginfo as *const () as usize, libc::SA_SIGINFO);
+      chained(&SIGINFO, libc::SIGINT);
+   });
+}
+
+// Off by default, since we can't know what the old one is going to do.
+#[test]
+fn unchained() {
+   in_child(|| {
+      set_handler(plain as *const () as usize, 0);
+      sigint_workaround(SigintWorkaround::Always);
+      let mut sigs = Signals::new(&[Signal::INT]);
+      send_to_thread(unsafe { libc::gettid() }, Signal::INT).unwrap();
+      assert_eq!(sigs.next_timeout(Duration::from_secs(5)), Ok(Some(Signal::INT)));
+      assert_eq!(PLAIN.load(Ordering::Relaxed), 0);
+   });
+}
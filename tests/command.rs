@@ -0,0 +1,69 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::unix::process::CommandExt as _;
+use std::process::Command;
+use std::ptr;
+
+use macluhan::{Backend, CommandExt, SigSet, Signal, Signals};
+
+use common::in_child;
+
+// One of the masks out of the child's `/proc/self/status`, by the time it's
+// gotten as far as running whatever we told it to.
+fn status(mut cmd: Command, field: &str) -> u64 {
+   let out = cmd.arg("-c").arg(format!("grep {field}: /proc/self/status")).output().unwrap();
+   assert!(out.status.success(), "{out:?}");
+   let out = String::from_utf8(out.stdout).unwrap();
+   let mask = out.trim().strip_prefix(field).and_then(|s| s.strip_prefix(':')).unwrap();
+   u64::from_str_radix(mask.trim(), 16).unwrap()
+}
+
+fn bit(sig: Signal) -> u64 {
+   1 << (sig.get() - 1)
+}
+
+// Checked again right before the exec, since by the time anyone can look at
+// `/proc` the kernel has already reset the handlers, and `std` may well have
+// emptied the mask too.
+fn sh(sig: Signal) -> Command {
+   let mut cmd = Command::new("sh");
+   cmd.reset_signals();
+   unsafe {
+      cmd.pre_exec(move || {
+         let mut act = MaybeUninit::<libc::sigaction>::uninit();
+         libc::sigaction(sig.get(), ptr::null(), act.as_mut_ptr());
+         if act.assume_init().sa_sigaction != libc::SIG_DFL {
+            return Err(io::Error::other("still handled"));
+         }
+         if common::blocked() != SigSet::empty() {
+            return Err(io::Error::other("still blocked"));
+         }
+         Ok(())
+      });
+   }
+   cmd
+}
+
+// Nothing blocked, whatever we happen to be watching.
+#[test]
+fn empty_mask() {
+   in_child(|| {
+      let _sigs = Signals::new(&[Signal::TERM, Signal::USR1, Signal::CHLD]);
+      assert_eq!(status(sh(Signal::USR1), "SigBlk"), 0);
+   });
+}
+
+// Our handler doesn't come along either, down to whatever was in place before
+// the exec.
+#[test]
+fn default_handler() {
+   in_child(|| {
+      let _sigs = Signals::with_backend(&[Signal::USR1], Backend::Handlers);
+      assert_eq!(status(sh(Signal::USR1), "SigCgt") & bit(Signal::USR1), 0);
+      assert_eq!(status(sh(Signal::USR1), "SigIgn") & bit(Signal::USR1), 0);
+   });
+}
@@ -0,0 +1,59 @@
+// Not every test uses all of it.
+#![allow(dead_code)]
+
+use std::mem::MaybeUninit;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use macluhan::SigSet;
+
+// Anything that goes to the whole process happens in a child that only has
+// the one thread. The test harness has plenty of its own, none of which have
+// anything blocked, so any of them could end up with the signal instead, and
+// then it'd just kill us. Whatever the child panics with still makes it to
+// stderr.
+//
+// `f` comes up with the exit code, and panicking makes it 101, same as a test
+// that fails.
+pub fn fork(f: impl FnOnce() -> i32) -> libc::pid_t {
+   match unsafe { libc::fork() } {
+      0 => {
+         let code = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(101);
+         unsafe { libc::_exit(code) };
+      },
+      pid => pid,
+   }
+}
+
+// The raw status, for anyone who cares how it went.
+pub fn wait(pid: libc::pid_t) -> libc::c_int {
+   let mut status = 0;
+   assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+   status
+}
+
+pub fn exit_code(status: libc::c_int) -> Option<i32> {
+   libc::WIFEXITED(status).then(|| libc::WEXITSTATUS(status))
+}
+
+// The usual, where the child passes as long as it doesn't panic.
+pub fn in_child(f: impl FnOnce()) {
+   let status = wait(fork(|| {
+      f();
+      0
+   }));
+   assert_eq!(exit_code(status), Some(0), "child went with status {status:#x}");
+}
+
+// Whatever's blocked on the current thread.
+pub fn blocked() -> SigSet {
+   let mut old = MaybeUninit::uninit();
+   let old = unsafe {
+      libc::pthread_sigmask(libc::SIG_BLOCK, ptr::null(), old.as_mut_ptr());
+      old.assume_init()
+   };
+   SigSet::full()
+      .iter()
+      .filter(|sig| unsafe { libc::sigismember(&old, sig.get()) } == 1)
+      .collect()
+}
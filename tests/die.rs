@@ -0,0 +1,38 @@
+mod common;
+
+use macluhan::{die_by, Signal, Signals};
+
+use common::fork;
+
+// How a child that was watching `sig` went after `die_by(sig)`. A stopped
+// child counts too, or else we'd be waiting on it forever.
+fn died_by(sig: Signal) -> libc::c_int {
+   let pid = fork(|| {
+      // Nobody needs the cores.
+      let none = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+      unsafe { libc::setrlimit(libc::RLIMIT_CORE, &none) };
+      let _sigs = Signals::new(&[sig]);
+      die_by(sig)
+   });
+   let mut status = 0;
+   assert_eq!(unsafe { libc::waitpid(pid, &mut status, libc::WUNTRACED) }, pid);
+   if libc::WIFSTOPPED(status) {
+      unsafe { libc::kill(pid, libc::SIGKILL) };
+      common::wait(pid);
+   }
+   status
+}
+
+// One of each, by what they'd do if nobody was around to catch them.
+#[test]
+fn by_default_action() {
+   for sig in [Signal::TERM, Signal::QUIT] {
+      let status = died_by(sig);
+      assert!(libc::WIFSIGNALED(status), "{sig:?} {status:#x}");
+      assert_eq!(libc::WTERMSIG(status), sig.get());
+   }
+   for sig in [Signal::TSTP, Signal::TTIN, Signal::TTOU, Signal::CHLD, Signal::WINCH] {
+      let status = died_by(sig);
+      assert_eq!(common::exit_code(status), Some(128 + sig.get()), "{sig:?} {status:#x}");
+   }
+}
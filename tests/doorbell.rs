@@ -0,0 +1,29 @@
+use macluhan::{
+   sigint_workaround, sigwait, Error, RtSignal, SigSet, SigintWorkaround, Signal, Signals,
+};
+
+// The doorbell comes off the top, leaving `SIGRTMIN` and up for the people
+// who count from there, and nobody gets to wait on it but us.
+#[test]
+fn top_of_the_range() {
+   sigint_workaround(SigintWorkaround::Always);
+   let top = Signal::rt_count() - 1;
+   let doorbell = Signal::rt(top).unwrap();
+   // Somebody already waiting on it, without having reserved it, which is
+   // only found out once `SIGINT` needs a doorbell.
+   let mut s = sigwait::Signals::new(&[doorbell]);
+   assert_eq!(s.add(&Signal::INT.into()), Err(Error::InUse(doorbell.get())));
+   assert_eq!(s.set(), doorbell.into());
+   drop(s);
+
+   let first = Signal::rt(0).unwrap();
+   let mut s = sigwait::Signals::new(&[Signal::INT, first]);
+   assert_eq!(RtSignal::reserve_at(top).err(), Some(Error::InUse(doorbell.get())));
+   assert!(RtSignal::reserve_at(0).is_ok());
+   assert_eq!(s.add(&doorbell.into()), Err(Error::InUse(doorbell.get())));
+   assert_eq!(s.set(), SigSet::from(Signal::INT).with(first));
+   let e = sigwait::Signals::try_new(&[Signal::TERM, doorbell]).err();
+   assert_eq!(e, Some(Error::InUse(doorbell.get())));
+   // Nor anybody else, since it's not blocked in between waits.
+   assert_eq!(Signals::try_new(&[doorbell]).err(), Some(Error::InUse(doorbell.get())));
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "std")]
+
+use std::os::fd::{AsFd, AsRawFd};
+
+use macluhan::{send_to_thread, sigint_workaround, Backend, SigintWorkaround, Signal, Signals};
+
+fn readable(fd: impl AsFd) -> bool {
+   let mut pfd = libc::pollfd { fd: fd.as_fd().as_raw_fd(), events: libc::POLLIN, revents: 0 };
+   match unsafe { libc::poll(&mut pfd, 1, 0) } {
+      0 => false,
+      1 => pfd.revents & libc::POLLIN != 0,
+      _ => panic!("poll: {}", std::io::Error::last_os_error()),
+   }
+}
+
+// Whatever's behind the fd, be it a signalfd, our handlers' eventfd, or the
+// epoll instance that goes with the `SIGINT` eventfd, it turns up readable in
+// somebody else's `poll` for everything we watch, and stops once it's all
+// been drained.
+#[test]
+fn pollable() {
+   let tid = unsafe { libc::gettid() };
+   for (b, workaround) in [
+      (Backend::Signalfd, SigintWorkaround::Always),
+      (Backend::Signalfd, SigintWorkaround::Never),
+      (Backend::Handlers, SigintWorkaround::Never),
+   ] {
+      sigint_workaround(workaround);
+      let mut s = Signals::with_backend(&[Signal::INT, Signal::USR1], b);
+      for sig in [Signal::INT, Signal::USR1] {
+         assert!(!readable(&s), "{b:?} {workaround:?}");
+         send_to_thread(tid, sig).unwrap();
+         assert!(readable(&s), "{b:?} {workaround:?} {sig:?}");
+         let got = s.drain().map(|info| info.unwrap().signal()).collect::<Vec<_>>();
+         assert_eq!(got, [sig], "{b:?} {workaround:?}");
+      }
+      assert!(!readable(&s), "{b:?} {workaround:?}");
+   }
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use macluhan::{
+   send_to_thread, sigint_workaround, sigwait, Backend, SigintWorkaround, Signal, Signals,
+};
+
+use common::in_child;
+
+// Straight to this thread, since the test harness has others lying around
+// that don't have anything blocked.
+fn raise_both() {
+   for sig in [Signal::INT, Signal::USR1] {
+      send_to_thread(unsafe { libc::gettid() }, sig).unwrap();
+   }
+}
+
+// Both kinds of `Signals`, so that there's one test for both.
+trait Forked {
+   fn reinit(&mut self);
+   fn drained(&mut self) -> Vec<Signal>;
+}
+
+impl Forked for Signals {
+   fn reinit(&mut self) {
+      self.reinit_after_fork().unwrap();
+   }
+
+   fn drained(&mut self) -> Vec<Signal> {
+      let mut got = self.drain().map(|info| info.unwrap().signal()).collect::<Vec<_>>();
+      got.sort();
+      got
+   }
+}
+
+impl Forked for sigwait::Signals {
+   fn reinit(&mut self) {
+      self.reinit_after_fork().unwrap();
+   }
+
+   fn drained(&mut self) -> Vec<Signal> {
+      let mut got = self.drain().map(|info| info.unwrap().signal()).collect::<Vec<_>>();
+      got.sort();
+      got
+   }
+}
+
+// Whatever's pending before the fork belongs to the parent, so the child
+// shouldn't see any of it, and the parent shouldn't see anything of the
+// child's.
+fn each_side(mut sigs: impl Forked, what: &str) {
+   raise_both();
+   in_child(|| {
+      sigs.reinit();
+      assert_eq!(sigs.drained(), [], "{what}");
+      raise_both();
+      assert_eq!(sigs.drained(), [Signal::INT, Signal::USR1], "{what}");
+   });
+   assert_eq!(sigs.drained(), [Signal::INT, Signal::USR1], "{what}");
+}
+
+// With and without the `SIGINT` eventfd, with our own handlers, and with no
+// fds at all.
+#[test]
+fn each_side_sees_its_own() {
+   let both = [Signal::INT, Signal::USR1];
+   for workaround in [SigintWorkaround::Always, SigintWorkaround::Never] {
+      sigint_workaround(workaround);
+      each_side(Signals::with_backend(&both, Backend::Signalfd), &format!("{workaround:?}"));
+      each_side(sigwait::Signals::new(&both), &format!("sigwait {workaround:?}"));
+   }
+   sigint_workaround(SigintWorkaround::Never);
+   each_side(Signals::with_backend(&both, Backend::Handlers), "handlers");
+}
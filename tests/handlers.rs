@@ -0,0 +1,104 @@
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use macluhan::{queue, send_to_thread, Backend, Signal, Signals};
+
+fn disposition(sig: Signal) -> usize {
+   let mut act = MaybeUninit::<libc::sigaction>::uninit();
+   unsafe {
+      libc::sigaction(sig.get(), ptr::null(), act.as_mut_ptr());
+      act.assume_init().sa_sigaction
+   }
+}
+
+// Everything shows up in the order it was sent, rather than lowest first like
+// with signalfd, and whatever was there before gets put back afterwards.
+#[test]
+fn forced() {
+   let sigs = [Signal::USR2, Signal::INT, Signal::USR1];
+   let mut s = Signals::with_backend(&sigs, Backend::Handlers);
+   assert_eq!(s.backend(), Backend::Handlers);
+   assert_eq!(s.check_threads(), Ok(()));
+   for sig in sigs {
+      send_to_thread(unsafe { libc::gettid() }, sig).unwrap();
+   }
+   let got = s.drain().map(|info| info.unwrap().signal()).collect::<Vec<_>>();
+   assert_eq!(got, sigs);
+
+   // The test harness has other threads, any of which could end up running
+   // the handler for something sent to the whole process, as long as nobody
+   // went and blocked it on all of them.
+   assert_eq!(s.block_threads(), Ok(()));
+   queue(unsafe { libc::getpid() }, Signal::USR1, 42).unwrap();
+   let info = s.next_info_timeout(Duration::from_secs(5)).unwrap().unwrap();
+   assert_eq!((info.signal(), info.value()), (Signal::USR1, Some(42)));
+   assert_eq!(info.pid(), unsafe { libc::getpid() });
+
+   s.remove(&Signal::USR2.into()).unwrap();
+   assert_eq!(disposition(Signal::USR2), libc::SIG_DFL);
+   drop(s);
+   assert!(sigs.iter().all(|&sig| disposition(sig) == libc::SIG_DFL));
+}
+
+// A bunch of threads all running the handler at once, so pushes finish out of
+// order, and every last one still has to come out the other end.
+#[test]
+fn crowded() {
+   let sig = Signal::rt(0).unwrap();
+   let mut s = Signals::with_backend(&[sig], Backend::Handlers);
+   assert_eq!(s.backend(), Backend::Handlers);
+   const THREADS: usize = 4;
+   const EACH: usize = 500;
+   let got = AtomicUsize::new(0);
+   thread::scope(|scope| {
+      for _ in 0..THREADS {
+         scope.spawn(|| {
+            let tid = unsafe { libc::gettid() };
+            for sent in 1..=EACH {
+               // Not so far ahead that the queue fills up and drops some.
+               while sent * THREADS > got.load(Ordering::Relaxed) + 16 {
+                  thread::yield_now();
+               }
+               send_to_thread(tid, sig).unwrap();
+            }
+         });
+      }
+      for _ in 0..THREADS * EACH {
+         let info = s.next_info_timeout(Duration::from_secs(5)).unwrap().unwrap();
+         assert_eq!(info.signal(), sig);
+         got.fetch_add(1, Ordering::Relaxed);
+      }
+   });
+   assert_eq!(s.next_now(), Ok(None));
+}
+
+// A `SIGIO` from the kernel has the band and the fd where a sent one would
+// have the pid and uid, so those had better not show up as either.
+#[test]
+fn kernel_generated() {
+   // Not in `libc` for glibc, but the same on every arch we'll run tests on.
+   const F_SETSIG: i32 = 10;
+   const F_SETOWN_EX: i32 = 15;
+   const F_OWNER_TID: i32 = 0;
+   const POLL_IN: i32 = 1;
+
+   let mut s = Signals::with_backend(&[Signal::IO], Backend::Handlers);
+   let mut fds = [0; 2];
+   assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+   // Just this thread, since the test harness's others could get it first.
+   let owner = [F_OWNER_TID, unsafe { libc::gettid() }];
+   unsafe {
+      assert_eq!(libc::fcntl(fds[0], F_SETOWN_EX, owner.as_ptr()), 0);
+      assert_eq!(libc::fcntl(fds[0], F_SETSIG, libc::SIGIO), 0);
+      assert_eq!(libc::fcntl(fds[0], libc::F_SETFL, libc::O_ASYNC), 0);
+      assert_eq!(libc::write(fds[1], b"x".as_ptr().cast(), 1), 1);
+   }
+   let info = s.next_info_timeout(Duration::from_secs(5)).unwrap().unwrap();
+   assert_eq!((info.signal(), info.code()), (Signal::IO, POLL_IN));
+   assert_eq!((info.pid(), info.uid()), (0, 0));
+   unsafe { libc::close(fds[0]) };
+   unsafe { libc::close(fds[1]) };
+}
@@ -0,0 +1,53 @@
+use macluhan::{
+   sigint_workaround, sigwait, Backend, Error, SigSet, SigintWorkaround, Signal, Signals,
+};
+
+// What a shell does to a background job, or `nohup` to everything. The presets
+// just do without, but asking for one by name is another matter, whichever
+// way we'd have gone about watching it.
+#[test]
+fn background_job() {
+   unsafe { libc::signal(libc::SIGINT, libc::SIG_IGN) };
+   unsafe { libc::signal(libc::SIGHUP, libc::SIG_IGN) };
+   let ignored = SigSet::from(Signal::INT).with(Signal::HUP);
+   for workaround in [SigintWorkaround::Always, SigintWorkaround::Never] {
+      sigint_workaround(workaround);
+      for (set, skipped) in [
+         Signals::try_deadly().map(|s| (s.set(), s.skipped())).unwrap(),
+         sigwait::Signals::try_deadly().map(|s| (s.set(), s.skipped())).unwrap(),
+      ] {
+         assert_eq!(set & ignored, SigSet::empty());
+         assert!(set.contains(Signal::TERM));
+         assert_eq!(skipped, ignored);
+      }
+      let s = Signals::try_all().unwrap();
+      assert!(!s.set().contains(Signal::INT));
+      assert_eq!(s.skipped(), ignored);
+      let s = sigwait::Signals::try_all().unwrap();
+      assert!(!s.set().contains(Signal::INT));
+      assert_eq!(s.skipped(), ignored);
+      drop(s);
+      assert_eq!(Signals::try_benign().unwrap().skipped(), SigSet::empty());
+      assert_eq!(Signals::try_new(&[Signal::TERM]).unwrap().skipped(), SigSet::empty());
+      for sig in ignored {
+         let sigs = SigSet::from(Signal::TERM).with(sig);
+         for b in [Backend::Signalfd, Backend::Handlers] {
+            let e = Signals::try_with_backend(sigs, b).err();
+            assert_eq!(e, Some(Error::Ignored(sig.get())), "{b:?}");
+         }
+         let e = sigwait::Signals::try_from_set(sigs).err();
+         assert_eq!(e, Some(Error::Ignored(sig.get())));
+      }
+      for b in [Backend::Signalfd, Backend::Handlers] {
+         let mut s = Signals::try_with_backend(Signal::TERM.into(), b).unwrap();
+         assert_eq!(s.backend(), b);
+         assert_eq!(s.add(&Signal::HUP.into()), Err(Error::Ignored(libc::SIGHUP)));
+         assert_eq!(s.set(), Signal::TERM.into());
+      }
+      let mut s = sigwait::Signals::try_new(&[Signal::TERM]).unwrap();
+      assert_eq!(s.add(&Signal::HUP.into()), Err(Error::Ignored(libc::SIGHUP)));
+      assert_eq!(s.set(), Signal::TERM.into());
+   }
+   unsafe { libc::signal(libc::SIGINT, libc::SIG_DFL) };
+   unsafe { libc::signal(libc::SIGHUP, libc::SIG_DFL) };
+}
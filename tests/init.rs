@@ -0,0 +1,81 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::process::Command;
+
+use macluhan::init::{self, Init};
+
+use common::{exit_code as code, fork, wait};
+
+// We're not PID 1, so this is all as a subreaper.
+fn in_child(f: impl FnOnce() -> i32) -> libc::c_int {
+   wait(fork(f))
+}
+
+fn sh(script: &str) -> Command {
+   let mut cmd = Command::new("sh");
+   cmd.args(["-c", script]);
+   cmd
+}
+
+// The inner shell leaves its `sleep` behind, which ends up with us and has to
+// be reaped along the way, while the command itself keeps going.
+#[test]
+fn orphans() {
+   let status = in_child(|| {
+      let status = Init::new(sh("sh -c 'sleep 0.1 &'; sleep 0.5; exit 7")).run().unwrap();
+      let mut raw = 0;
+      let left = unsafe { libc::waitpid(-1, &mut raw, libc::WNOHANG) };
+      match (status.code(), left, io::Error::last_os_error().raw_os_error()) {
+         (Some(7), -1, Some(libc::ECHILD)) => 0,
+         _ => 1,
+      }
+   });
+   assert_eq!(code(status), Some(0));
+}
+
+#[test]
+fn forwarding() {
+   let mut fds = [0; 2];
+   assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+   let pid = fork(|| {
+      unsafe { libc::close(fds[0]) };
+      let mut cmd = sh("trap 'exit 9' TERM; echo; while :; do sleep 0.01; done");
+      cmd.stdout(unsafe { OwnedFd::from_raw_fd(fds[1]) });
+      match Init::new(cmd).run() {
+         Ok(status) => status.code().unwrap_or(1),
+         Err(_) => 1,
+      }
+   });
+   unsafe { libc::close(fds[1]) };
+   let rx = unsafe { File::from_raw_fd(fds[0]) };
+   // The command is up, so `Init` has everything blocked by now.
+   let mut buf = [0u8];
+   (&rx).read_exact(&mut buf).unwrap();
+   assert_eq!(unsafe { libc::kill(pid, libc::SIGTERM) }, 0);
+   assert_eq!(code(wait(pid)), Some(9));
+}
+
+// However the command went, we go the same way, unless it never got going.
+#[test]
+fn exit() {
+   let status = in_child(|| {
+      let _ = init::run(sh("exit 7"));
+      1
+   });
+   assert_eq!(code(status), Some(7));
+   let status = in_child(|| {
+      let _ = init::run(sh("kill -TERM $$"));
+      1
+   });
+   assert!(libc::WIFSIGNALED(status) && libc::WTERMSIG(status) == libc::SIGTERM);
+   let status = in_child(|| match init::run(Command::new("/nonexistent")).kind() {
+      io::ErrorKind::NotFound => 0,
+      _ => 1,
+   });
+   assert_eq!(code(status), Some(0));
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::time::Duration;
+
+use macluhan::{send_to_thread, SigSet, Signal, Signals};
+
+use common::{blocked, in_child};
+
+// Two of them watching the same signal on the same thread, and whichever goes
+// first leaves it blocked for the other one. If it doesn't, the `SIGTERM`
+// kills the child, which is why there's a child.
+#[test]
+fn overlapping() {
+   in_child(|| {
+      let a = Signals::new(&[Signal::TERM]);
+      let mut b = Signals::new(&[Signal::TERM, Signal::USR1]);
+      drop(a);
+      assert!(blocked().contains(Signal::TERM));
+      send_to_thread(unsafe { libc::gettid() }, Signal::TERM).unwrap();
+      assert_eq!(b.next_timeout(Duration::from_secs(5)), Ok(Some(Signal::TERM)));
+      drop(b);
+      assert_eq!(blocked(), SigSet::empty());
+   });
+}
+
+// Whatever was blocked before we got there isn't ours to unblock.
+#[test]
+fn not_ours() {
+   in_child(|| {
+      let usr2 = SigSet::from(Signal::USR2);
+      unsafe {
+         let mut set = MaybeUninit::uninit();
+         libc::sigemptyset(set.as_mut_ptr());
+         libc::sigaddset(set.as_mut_ptr(), libc::SIGUSR2);
+         libc::pthread_sigmask(libc::SIG_BLOCK, set.as_ptr(), ptr::null_mut());
+      }
+      let mut a = Signals::new(&[Signal::USR1, Signal::USR2]);
+      let b = Signals::new(&[Signal::USR2]);
+      a.remove(&usr2).unwrap();
+      drop(b);
+      drop(a);
+      assert_eq!(blocked(), usr2);
+   });
+}
@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use macluhan::{send_to_thread, sigint_workaround, Backend, SigintWorkaround, Signal, Signals};
+
+// `SIGINT` goes around the signalfd through its own handler, but still comes
+// out in between whatever was already pending and whatever came after, with
+// everything a signalfd would've said about it.
+#[test]
+fn sigint_in_between() {
+   sigint_workaround(SigintWorkaround::Always);
+   let sigs = [Signal::INT, Signal::USR1, Signal::USR2];
+   let mut s = Signals::with_backend(&sigs, Backend::Signalfd);
+   assert_eq!(s.backend(), Backend::Signalfd);
+   let (me, tid) = unsafe { (libc::getpid(), libc::gettid()) };
+   send_to_thread(tid, Signal::USR2).unwrap();
+   send_to_thread(tid, Signal::INT).unwrap();
+   send_to_thread(tid, Signal::USR1).unwrap();
+
+   let mut next = || s.next_info_timeout(Duration::ZERO).unwrap().unwrap();
+   assert_eq!(next().signal(), Signal::USR2);
+   let info = next();
+   const SI_TKILL: i32 = -6; // Not in `libc` either
+   assert_eq!((info.signal(), info.pid(), info.code()), (Signal::INT, me, SI_TKILL));
+   assert_eq!(info.uid(), unsafe { libc::getuid() });
+   assert_eq!(next().signal(), Signal::USR1);
+   assert_eq!(s.next_now(), Ok(None));
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use std::mem::MaybeUninit;
+use std::{ptr, thread};
+
+use macluhan::{Signal, Signals};
+
+use common::{exit_code, fork, wait};
+
+// Whether the probe left a handler behind, i.e. went for the workaround, in a
+// fresh child that hasn't probed yet, with or without somebody reaping
+// anything they can get their hands on the whole time.
+fn probe(reaping: bool) -> bool {
+   let pid = fork(|| {
+      if reaping {
+         thread::spawn(|| loop {
+            unsafe { libc::waitpid(-1, ptr::null_mut(), 0) };
+         });
+      }
+      let _sigs = Signals::new(&[Signal::INT]);
+      let mut act = MaybeUninit::<libc::sigaction>::uninit();
+      unsafe { libc::sigaction(libc::SIGINT, ptr::null(), act.as_mut_ptr()) };
+      (unsafe { act.assume_init() }.sa_sigaction != libc::SIG_DFL) as i32
+   });
+   match exit_code(wait(pid)) {
+      Some(handled @ (0 | 1)) => handled == 1,
+      code => panic!("probe child went with {code:?}"),
+   }
+}
+
+#[test]
+fn reaped_elsewhere() {
+   assert_eq!(probe(true), probe(false));
+}
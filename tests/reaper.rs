@@ -0,0 +1,95 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use macluhan::{ChildReaper, ChildStatus, Signal, Signals};
+
+use common::in_child;
+
+fn fork_exit(code: i32) -> libc::pid_t {
+   match unsafe { libc::fork() } {
+      0 => unsafe { libc::_exit(code) },
+      pid => pid,
+   }
+}
+
+// They've all exited by the time we look, so there's only the one `SIGCHLD`
+// to go around, but everyone still gets reaped.
+#[test]
+fn coalesced() {
+   in_child(|| {
+      let mut sigs = Signals::new(&[Signal::CHLD]);
+      let mut reaper = ChildReaper::new();
+      let mut pids = (0..5).map(|code| (fork_exit(code), code)).collect::<Vec<_>>();
+      thread::sleep(Duration::from_millis(200));
+      assert_eq!(sigs.next_now(), Ok(Some(Signal::CHLD)));
+      assert_eq!(sigs.next_now(), Ok(None));
+
+      assert_eq!(reaper.handle(Signal::HUP).count(), 0);
+      let mut reaped = reaper
+         .handle(Signal::CHLD)
+         .map(|r| r.map(|(pid, status)| (pid, status.code().unwrap())))
+         .collect::<Result<Vec<_>, _>>()
+         .unwrap();
+      pids.sort();
+      reaped.sort();
+      assert_eq!(reaped, pids);
+      // Nobody left, which isn't an error.
+      assert_eq!(reaper.reap().count(), 0);
+   });
+}
+
+// Only what it's told about, so `std::process::Child` still works.
+#[test]
+fn tracked() {
+   in_child(|| {
+      let mut reaper = ChildReaper::tracked();
+      let mut child = Command::new("sh").args(["-c", "exit 3"]).spawn().unwrap();
+      let (mine, other, stolen) = (fork_exit(4), fork_exit(5), fork_exit(6));
+      reaper.track(mine);
+      reaper.track(other);
+      reaper.untrack(other);
+      reaper.track(stolen);
+      let mut status = 0;
+      assert_eq!(unsafe { libc::waitpid(stolen, &mut status, 0) }, stolen);
+
+      thread::sleep(Duration::from_millis(200));
+      let reaped = reaper.reap().collect::<Result<Vec<_>, _>>().unwrap();
+      assert_eq!(reaped, [(mine, ChildStatus::from_raw(4 << 8))]);
+      assert_eq!(reaper.reap().count(), 0);
+      assert_eq!(child.wait().unwrap().code(), Some(3));
+      assert_eq!(unsafe { libc::waitpid(other, &mut status, 0) }, other);
+      assert_eq!(ChildStatus::from_raw(status).code(), Some(5));
+   });
+}
+
+#[test]
+fn status() {
+   in_child(|| {
+      let mut reaper = ChildReaper::new();
+      let pid = match unsafe { libc::fork() } {
+         0 => loop {
+            unsafe { libc::pause() };
+         },
+         pid => pid,
+      };
+      unsafe { libc::kill(pid, libc::SIGTERM) };
+      thread::sleep(Duration::from_millis(200));
+      let (reaped, status) = reaper.reap().next().unwrap().unwrap();
+      assert_eq!(reaped, pid);
+      assert_eq!((status.code(), status.signal()), (None, Some(Signal::TERM)));
+      assert!(!status.success() && !status.core_dumped());
+      assert_eq!(status.to_string(), "signal: SIGTERM");
+
+      fork_exit(0);
+      thread::sleep(Duration::from_millis(200));
+      let (_, status) = reaper.reap().next().unwrap().unwrap();
+      assert!(status.success());
+      assert_eq!(format!("{status:?}"), "ChildStatus(exit status: 0)");
+      assert!(std::process::ExitStatus::from(status).success());
+   });
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use macluhan::{queue, queue_ptr, Error, RtSignal, Signal, Signals};
+
+use common::in_child;
+
+// Reservations are process-wide, and a child gets whatever was reserved at the
+// time of the `fork`, so the tests take turns.
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[test]
+fn reservations() {
+   let _lock = LOCK.lock().unwrap();
+   let a = RtSignal::reserve().unwrap();
+   let b = RtSignal::reserve().unwrap();
+   assert_ne!(a.signal(), b.signal());
+   assert!(a.signal().is_rt() && b.signal().is_rt());
+
+   let n = a.signal().rt_offset().unwrap();
+   assert_eq!(RtSignal::reserve_at(n).unwrap_err(), Error::InUse(a.signal().get()));
+   drop(a);
+   let a = RtSignal::reserve_at(n).unwrap();
+   assert_eq!(a.signal().rt_offset(), Some(n));
+   assert!(RtSignal::reserve_at(-1).is_err());
+   assert!(RtSignal::reserve_at(Signal::rt_count()).is_err());
+
+   // Everything that's left, and then nothing, until somebody gives one back.
+   let rest = (0..).map_while(|_| RtSignal::reserve().ok()).collect::<Vec<_>>();
+   assert_eq!(rest.len() + 2, Signal::rt_count() as usize);
+   assert_eq!(RtSignal::reserve().unwrap_err(), Error::RtExhausted);
+   let last = rest.last().unwrap().signal();
+   drop(rest);
+   drop(b);
+   assert!(RtSignal::reserve_at(last.rt_offset().unwrap()).is_ok());
+}
+
+// Same signal, same order they were sent in, payloads and all. Queued to our
+// own process, so it happens in a child.
+#[test]
+fn payloads_in_order() {
+   let _lock = LOCK.lock().unwrap();
+   in_child(|| {
+      let me = unsafe { libc::getpid() };
+      let rt = RtSignal::reserve().unwrap();
+      let mut sigs = Signals::new(&[rt.signal()]);
+      for value in 1..=20 {
+         queue(me, rt.signal(), value).unwrap();
+      }
+      queue_ptr(me, rt.signal(), usize::MAX).unwrap();
+      let mut next = || sigs.next_info_timeout(Duration::from_secs(5)).unwrap().unwrap();
+      for value in 1..=20 {
+         let info = next();
+         assert_eq!((info.signal(), info.value()), (rt.signal(), Some(value)));
+      }
+      assert_eq!(next().ptr(), usize::MAX as u64);
+      assert_eq!(sigs.next_now(), Ok(None));
+   });
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use std::time::Duration;
+
+use macluhan::{
+   queue, queue_ptr, send, send_to_group, send_to_thread, Error, PidFd, Signal, SignalInfo, Signals,
+};
+
+use common::in_child;
+
+fn next(sigs: &mut Signals) -> SignalInfo {
+   sigs.next_info_timeout(Duration::from_secs(5)).unwrap().unwrap()
+}
+
+// 0 and negative pids mean groups to `kill`, which is what `send_to_group` is
+// for.
+#[test]
+fn invalid_pids() {
+   for pid in [0, -1, -42] {
+      assert_eq!(send(pid, Signal::USR1), Err(Error::InvalidPid(pid)));
+      assert_eq!(send_to_thread(pid, Signal::USR1), Err(Error::InvalidPid(pid)));
+      assert_eq!(queue(pid, Signal::USR1, 0), Err(Error::InvalidPid(pid)));
+      assert_eq!(queue_ptr(pid, Signal::USR1, 0), Err(Error::InvalidPid(pid)));
+      assert!(PidFd::open(pid).is_err());
+   }
+   assert_eq!(send_to_group(-1, Signal::USR1), Err(Error::InvalidPid(-1)));
+}
+
+#[test]
+fn everything_arrives() {
+   in_child(|| {
+      let me = unsafe { libc::getpid() };
+      let rt = Signal::rt(0).unwrap();
+      let mut sigs = Signals::new(&[Signal::HUP, Signal::USR1, Signal::USR2, Signal::TERM, rt]);
+
+      send(me, Signal::USR1).unwrap();
+      let info = next(&mut sigs);
+      const SI_USER: i32 = 0; // Not in `libc` either
+      assert_eq!((info.signal(), info.pid(), info.code()), (Signal::USR1, me, SI_USER));
+      assert!(info.is_user() && info.value().is_none());
+
+      queue(me, Signal::USR2, -7).unwrap();
+      let info = next(&mut sigs);
+      assert_eq!((info.signal(), info.value(), info.int()), (Signal::USR2, Some(-7), -7));
+      assert_eq!(info.pid(), me);
+
+      queue_ptr(me, rt, 0xdead_beef).unwrap();
+      let info = next(&mut sigs);
+      assert_eq!((info.signal(), info.ptr()), (rt, 0xdead_beef));
+
+      // Our own group, rather than whoever's running the tests.
+      assert_eq!(unsafe { libc::setpgid(0, 0) }, 0);
+      send_to_group(0, Signal::HUP).unwrap();
+      assert_eq!(sigs.next_now(), Ok(Some(Signal::HUP)));
+
+      // Not every kernel has them, and neither does every qemu.
+      match PidFd::open(me) {
+         Ok(pidfd) => {
+            pidfd.send(Signal::TERM).unwrap();
+            assert_eq!(sigs.next_now(), Ok(Some(Signal::TERM)));
+         },
+         Err(Error::Os { errno, .. }) if errno == libc::ENOSYS => (),
+         Err(e) => panic!("{e}"),
+      }
+      assert_eq!(sigs.next_now(), Ok(None));
+      let e = send(i32::MAX, Signal::USR1);
+      assert!(matches!(e, Err(Error::Os { call: "kill", errno }) if errno == libc::ESRCH));
+   });
+}
@@ -0,0 +1,204 @@
+#![cfg(feature = "std")]
+
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use macluhan::{Policy, Shutdown, Signal};
+
+use common::{fork, wait};
+
+// Everything happens in a child, since the whole point is for the process to
+// go away, and it reports back over a pipe.
+struct Child {
+   pid: libc::pid_t,
+   rx: i32,
+}
+
+fn spawn(f: impl FnOnce(i32)) -> Child {
+   let mut fds = [0; 2];
+   assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+   let pid = fork(|| {
+      unsafe { libc::close(fds[0]) };
+      f(fds[1]);
+      99
+   });
+   unsafe { libc::close(fds[1]) };
+   Child { pid, rx: fds[0] }
+}
+
+fn tell(tx: i32, b: u8) {
+   assert_eq!(unsafe { libc::write(tx, (&b as *const u8).cast(), 1) }, 1);
+}
+
+impl Child {
+   fn hear(&self) -> Option<u8> {
+      let mut b = 0u8;
+      match unsafe { libc::read(self.rx, (&mut b as *mut u8).cast(), 1) } {
+         1 => Some(b),
+         _ => None,
+      }
+   }
+
+   fn send(&self, sig: Signal) {
+      assert_eq!(unsafe { libc::kill(self.pid, sig.get()) }, 0);
+   }
+
+   fn alive_after(&self, wait: Duration) -> bool {
+      thread::sleep(wait);
+      let mut status = 0;
+      unsafe { libc::waitpid(self.pid, &mut status, libc::WNOHANG) == 0 }
+   }
+
+   fn status(self) -> i32 {
+      let status = wait(self.pid);
+      unsafe { libc::close(self.rx) };
+      status
+   }
+
+   fn exit_code(self) -> Option<i32> {
+      common::exit_code(self.status())
+   }
+}
+
+// Ready, then draining, then it's up to the watchdog.
+fn drain_forever(shutdown: Shutdown, tx: i32) -> ! {
+   tell(tx, b'r');
+   let draining = shutdown.wait().unwrap();
+   tell(tx, draining.signal().get() as u8);
+   loop {
+      thread::sleep(Duration::from_secs(60));
+   }
+}
+
+#[test]
+fn exit_and_die() {
+   let child = spawn(|tx| {
+      let shutdown = Shutdown::new();
+      tell(tx, b'r');
+      shutdown.wait().unwrap().exit();
+   });
+   assert_eq!(child.hear(), Some(b'r'));
+   child.send(Signal::TERM);
+   assert_eq!(child.exit_code(), Some(128 + libc::SIGTERM));
+
+   let child = spawn(|tx| {
+      let shutdown = Shutdown::new();
+      tell(tx, b'r');
+      shutdown.wait().unwrap().die();
+   });
+   assert_eq!(child.hear(), Some(b'r'));
+   child.send(Signal::TERM);
+   let status = child.status();
+   assert!(libc::WIFSIGNALED(status) && libc::WTERMSIG(status) == libc::SIGTERM);
+}
+
+// The default is that the second one forces things, and whichever signal does
+// that is the one that gets reported.
+#[test]
+fn escalation() {
+   let child = spawn(|tx| drain_forever(Shutdown::new(), tx));
+   assert_eq!(child.hear(), Some(b'r'));
+   child.send(Signal::TERM);
+   assert_eq!(child.hear(), Some(libc::SIGTERM as u8));
+   assert!(child.alive_after(Duration::from_millis(100)));
+   child.send(Signal::HUP);
+   assert_eq!(child.exit_code(), Some(128 + libc::SIGHUP));
+
+   let child = spawn(|tx| drain_forever(Shutdown::new().escalate_after(3), tx));
+   assert_eq!(child.hear(), Some(b'r'));
+   child.send(Signal::TERM);
+   assert_eq!(child.hear(), Some(libc::SIGTERM as u8));
+   child.send(Signal::TERM);
+   child.send(Signal::HUP);
+   assert!(child.alive_after(Duration::from_millis(100)));
+   child.send(Signal::INT);
+   assert_eq!(child.exit_code(), Some(128 + libc::SIGINT));
+}
+
+// With escalation off, only the grace period can end things, and it goes by
+// the first signal.
+#[test]
+fn grace() {
+   let child = spawn(|tx| {
+      drain_forever(Shutdown::new().escalate_after(0).grace(Duration::from_millis(300)), tx)
+   });
+   assert_eq!(child.hear(), Some(b'r'));
+   let start = Instant::now();
+   child.send(Signal::TERM);
+   assert_eq!(child.hear(), Some(libc::SIGTERM as u8));
+   for sig in [Signal::HUP, Signal::INT, Signal::TERM] {
+      child.send(sig);
+   }
+   assert_eq!(child.exit_code(), Some(128 + libc::SIGTERM));
+   assert!(start.elapsed() >= Duration::from_millis(300));
+}
+
+#[test]
+fn policies() {
+   let policy = |shutdown: Shutdown| {
+      shutdown.policy(Signal::HUP, Policy::Ignore).policy(Signal::QUIT, Policy::Force)
+   };
+
+   // Straight out, without ever draining.
+   let child = spawn(|tx| drain_forever(policy(Shutdown::new()), tx));
+   assert_eq!(child.hear(), Some(b'r'));
+   child.send(Signal::HUP);
+   child.send(Signal::QUIT);
+   assert_eq!(child.hear(), None);
+   assert_eq!(child.exit_code(), Some(128 + libc::SIGQUIT));
+
+   // And ignored while draining, too.
+   let child = spawn(|tx| drain_forever(policy(Shutdown::new()), tx));
+   assert_eq!(child.hear(), Some(b'r'));
+   child.send(Signal::TERM);
+   assert_eq!(child.hear(), Some(libc::SIGTERM as u8));
+   child.send(Signal::HUP);
+   assert!(child.alive_after(Duration::from_millis(100)));
+   child.send(Signal::QUIT);
+   assert_eq!(child.exit_code(), Some(128 + libc::SIGQUIT));
+}
+
+struct Flag(AtomicBool);
+
+impl Wake for Flag {
+   fn wake(self: Arc<Self>) {
+      self.0.store(true, Ordering::Relaxed);
+   }
+}
+
+// Everybody holding a token hears about it, however they're waiting.
+#[test]
+fn token() {
+   let child = spawn(|tx| {
+      let shutdown = Shutdown::new().escalate_after(0);
+      let token = shutdown.token();
+      let waiter = thread::spawn({
+         let token = token.clone();
+         move || token.wait()
+      });
+      let flag = Arc::new(Flag(AtomicBool::new(false)));
+      let waker = Waker::from(flag.clone());
+      let mut cx = Context::from_waker(&waker);
+      let ok = token.poll_cancelled(&mut cx).is_pending()
+         && !token.is_cancelled()
+         && token.wait_timeout(Duration::from_millis(10)).is_none();
+      tell(tx, ok as u8);
+      let draining = shutdown.wait().unwrap();
+      let ok = waiter.join().unwrap() == Signal::USR1
+         && flag.0.load(Ordering::Relaxed)
+         && token.poll_cancelled(&mut cx) == Poll::Ready(Signal::USR1)
+         && token.signal() == Some(Signal::USR1)
+         && draining.token().wait_timeout(Duration::ZERO) == Some(Signal::USR1);
+      tell(tx, ok as u8);
+      unsafe { libc::_exit(0) };
+   });
+   assert_eq!(child.hear(), Some(1));
+   child.send(Signal::USR1);
+   assert_eq!(child.hear(), Some(1));
+   assert_eq!(child.exit_code(), Some(0));
+}
@@ -0,0 +1,101 @@
+use macluhan::{Error, Signal};
+
+fn parse(s: &str) -> Result<Signal, Error> {
+   s.parse()
+}
+
+#[test]
+fn names() {
+   for s in ["TERM", "SIGTERM", "sigterm", "SigTerm", "15"] {
+      assert_eq!(parse(s), Ok(Signal::TERM), "{s}");
+   }
+   for s in ["", "SIG", "FOO", "SIGFOO", "TERMS", " TERM", "1.0"] {
+      assert_eq!(parse(s), Err(Error::UnknownSignal), "{s}");
+   }
+   assert_eq!(Signal::TERM.to_string(), "SIGTERM");
+   assert_eq!(format!("{:?}", Signal::CHLD), "SIGCHLD");
+   assert_eq!(Signal::INT.description(), "Interrupt");
+}
+
+// Fine for sending, but they never come out of anything, so they're not
+// `Signal`s.
+#[test]
+fn kill_and_stop() {
+   for s in ["KILL", "SIGKILL", "9"] {
+      assert_eq!(parse(s), Err(Error::InvalidSignal(libc::SIGKILL)), "{s}");
+   }
+   assert_eq!(parse("STOP"), Err(Error::InvalidSignal(libc::SIGSTOP)));
+   assert_eq!(Signal::new(libc::SIGKILL), Err(Error::InvalidSignal(libc::SIGKILL)));
+   assert_eq!(Signal::new(libc::SIGSTOP), Err(Error::InvalidSignal(libc::SIGSTOP)));
+}
+
+#[test]
+fn out_of_range() {
+   let rtmin = libc::SIGRTMIN();
+   let rtmax = libc::SIGRTMAX();
+   for sig in [0, -1, 32, rtmin - 1, rtmax + 1, libc::c_int::MAX] {
+      assert_eq!(Signal::new(sig), Err(Error::InvalidSignal(sig)), "{sig}");
+   }
+   assert_eq!(Signal::try_from(libc::SIGHUP), Ok(Signal::HUP));
+   assert_eq!(Signal::try_from(0), Err(Error::InvalidSignal(0)));
+   // The sentinel for `SIGSTKFLT` on architectures without one doesn't get to
+   // sneak in through the names either.
+   assert_eq!(parse("-1"), Err(Error::InvalidSignal(-1)));
+   #[cfg(not(any(
+      target_arch = "mips",
+      target_arch = "mips32r6",
+      target_arch = "mips64",
+      target_arch = "mips64r6",
+      target_arch = "sparc",
+      target_arch = "sparc64",
+   )))]
+   assert_eq!(parse("STKFLT").map(Signal::get), Ok(libc::SIGSTKFLT));
+   #[cfg(any(
+      target_arch = "mips",
+      target_arch = "mips32r6",
+      target_arch = "mips64",
+      target_arch = "mips64r6",
+      target_arch = "sparc",
+      target_arch = "sparc64",
+   ))]
+   assert_eq!(parse("STKFLT"), Err(Error::UnknownSignal));
+}
+
+#[test]
+fn realtime() {
+   let n = Signal::rt_count();
+   assert!(n > 0);
+   let first = Signal::rt(0).unwrap();
+   let last = Signal::rt(n - 1).unwrap();
+   assert_eq!(first.get(), libc::SIGRTMIN());
+   assert_eq!(last.get(), libc::SIGRTMAX());
+   assert!(first.is_rt() && !Signal::TERM.is_rt());
+   assert_eq!((last.rt_offset(), Signal::TERM.rt_offset()), (Some(n - 1), None));
+   assert!(Signal::rt(n).is_err() && Signal::rt(-1).is_err());
+
+   assert_eq!(first.to_string(), "SIGRTMIN");
+   assert_eq!(Signal::rt(3).unwrap().to_string(), "SIGRTMIN+3");
+   assert_eq!(first.description(), "Real-time signal");
+
+   assert_eq!(parse("RTMIN"), Ok(first));
+   assert_eq!(parse("SIGRTMIN+3"), Signal::rt(3));
+   assert_eq!(parse("rtmin+0"), Ok(first));
+   assert_eq!(parse("RTMAX"), Ok(last));
+   assert_eq!(parse("SIGRTMAX-1"), Signal::rt(n - 2));
+   for s in ["RTMIN+", "RTMIN-1", "RTMAX+1", "RTMAX-", "RTMIN+x", "RTMIN+256", "RTMINUS"] {
+      assert_eq!(parse(s), Err(Error::UnknownSignal), "{s}");
+   }
+   assert_eq!(parse("RTMIN+100"), Err(Error::InvalidSignal(libc::SIGRTMIN() + 100)));
+   assert_eq!(parse("RTMAX-100"), Err(Error::InvalidSignal(libc::SIGRTMAX() - 100)));
+}
+
+// Whatever comes out has to go back in.
+#[test]
+fn round_trip() {
+   let rt = (0..Signal::rt_count()).map(|n| Signal::rt(n).unwrap());
+   let std = (1..32).filter_map(|sig| Signal::new(sig).ok());
+   for sig in std.chain(rt) {
+      assert_eq!(parse(&sig.to_string()), Ok(sig));
+      assert_eq!(parse(&sig.get().to_string()), Ok(sig));
+   }
+}
@@ -0,0 +1,127 @@
+use std::mem::MaybeUninit;
+
+use macluhan::{SigSet, Signal};
+
+// Everything from 1 to 31 other than `SIGKILL` and `SIGSTOP`, on every
+// architecture, plus however many realtime signals libc left us.
+fn every() -> impl Iterator<Item = Signal> {
+   (1..=libc::SIGRTMAX()).filter_map(|sig| Signal::new(sig).ok())
+}
+
+#[test]
+fn empty_and_full() {
+   assert!(SigSet::empty().is_empty());
+   assert_eq!(SigSet::empty().len(), 0);
+   assert_eq!(SigSet::default(), SigSet::empty());
+   assert_eq!(SigSet::full().len(), 29 + Signal::rt_count() as usize);
+   assert!(SigSet::full().iter().eq(every()));
+   assert_eq!(!SigSet::empty(), SigSet::full());
+   assert_eq!(!SigSet::full(), SigSet::empty());
+}
+
+#[test]
+fn algebra() {
+   let a = SigSet::from([Signal::TERM, Signal::HUP, Signal::INT]);
+   let b = SigSet::from(&[Signal::INT, Signal::USR1][..]);
+   assert_eq!(a | b, SigSet::from([Signal::HUP, Signal::INT, Signal::USR1, Signal::TERM]));
+   assert_eq!(a & b, Signal::INT.into());
+   assert_eq!(a - b, SigSet::from([Signal::HUP, Signal::TERM]));
+   assert_eq!(b - a, Signal::USR1.into());
+   assert_eq!(a - a, SigSet::empty());
+   assert_eq!(!!a, a);
+   assert_eq!(!(a | b), !a & !b);
+   assert_eq!(!(a & b), !a | !b);
+   assert_eq!((!a).len(), SigSet::full().len() - 3);
+   assert!(!(!a).contains(Signal::TERM) && (!a).contains(Signal::USR2));
+
+   // Lowest first, regardless of how they went in.
+   assert!(a.iter().eq([Signal::HUP, Signal::INT, Signal::TERM]));
+   assert_eq!(format!("{a:?}"), "{SIGHUP, SIGINT, SIGTERM}");
+
+   let mut c = SigSet::empty();
+   c.insert(Signal::USR1);
+   c.extend([Signal::USR2, Signal::USR1]);
+   assert_eq!(c.len(), 2);
+   c.remove(Signal::USR1);
+   assert!(!c.contains(Signal::USR1) && c.contains(Signal::USR2));
+   assert_eq!(c.with(Signal::USR1).without(Signal::USR2), Signal::USR1.into());
+   let rt = Signal::rt(0).unwrap();
+   assert_eq!(SigSet::from(rt).iter().collect::<Vec<_>>(), [rt]);
+}
+
+// Only actual signals count. On glibc, `sigfillset` leaves out the couple of
+// realtime signals it keeps for itself but still fills in the other 900-odd
+// bits nobody uses, and `SIGKILL` and `SIGSTOP` are in there too.
+#[test]
+fn eq_ignores_the_rest() {
+   let mut raw = MaybeUninit::uninit();
+   let raw = unsafe {
+      libc::sigfillset(raw.as_mut_ptr());
+      raw.assume_init()
+   };
+   let filled = every().filter(|sig| unsafe { libc::sigismember(&raw, sig.get()) } == 1);
+   assert!(SigSet::full().iter().eq(filled));
+   assert_eq!(SigSet::full(), every().collect());
+}
+
+// What `Signals::deadly` has always meant, from back when it was written with
+// `sigfillset` and `sigdelset`.
+#[test]
+fn presets() {
+   let mut raw = MaybeUninit::uninit();
+   let raw = unsafe {
+      libc::sigfillset(raw.as_mut_ptr());
+      for sig in [
+         libc::SIGBUS,
+         libc::SIGFPE,
+         libc::SIGILL,
+         libc::SIGSEGV,
+         libc::SIGPIPE,
+         libc::SIGCHLD,
+         libc::SIGURG,
+         libc::SIGWINCH,
+      ] {
+         libc::sigdelset(raw.as_mut_ptr(), sig);
+      }
+      raw.assume_init()
+   };
+   let baseline = every().filter(|sig| unsafe { libc::sigismember(&raw, sig.get()) } == 1);
+   assert!(SigSet::deadly().iter().eq(baseline));
+   assert_eq!(SigSet::deadly(), SigSet::all() - SigSet::benign());
+   assert_eq!(SigSet::all() & SigSet::benign(), SigSet::benign());
+   for sig in [Signal::BUS, Signal::FPE, Signal::ILL, Signal::SEGV, Signal::PIPE] {
+      assert!(!SigSet::all().contains(sig), "{sig}");
+   }
+
+   let presets = [
+      SigSet::terminating(),
+      SigSet::core_dumping(),
+      SigSet::benign(),
+      SigSet::job_control(),
+   ];
+   for (i, a) in presets.iter().enumerate() {
+      for b in &presets[i + 1..] {
+         assert_eq!(*a & *b, SigSet::empty(), "{a:?} {b:?}");
+      }
+   }
+   // MIPS and SPARC have `SIGEMT`, which doesn't go anywhere in particular.
+   #[cfg(not(any(
+      target_arch = "mips",
+      target_arch = "mips32r6",
+      target_arch = "mips64",
+      target_arch = "mips64r6",
+      target_arch = "sparc",
+      target_arch = "sparc64",
+   )))]
+   assert_eq!(presets.into_iter().fold(SigSet::empty(), |a, b| a | b), SigSet::full());
+   assert!(SigSet::terminating().contains(Signal::rt(0).unwrap()));
+   assert_eq!(SigSet::reload(), Signal::HUP.into());
+}
+
+#[test]
+fn ignored() {
+   unsafe { libc::signal(libc::SIGUSR2, libc::SIG_IGN) };
+   assert!(SigSet::ignored().contains(Signal::USR2));
+   unsafe { libc::signal(libc::SIGUSR2, libc::SIG_DFL) };
+   assert!(!SigSet::ignored().contains(Signal::USR2));
+}
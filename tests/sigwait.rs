@@ -0,0 +1,101 @@
+mod common;
+
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use macluhan::{
+   send, send_to_thread, sigint_workaround, sigwait, Error, SigintWorkaround, Signal, Signals,
+};
+
+use common::{blocked, in_child};
+
+// There's only the one `SIGINT` to go around, and `out_of_fds` has everybody
+// else out of fds while it's at it.
+static LOCK: Mutex<()> = Mutex::new(());
+
+// Out of fds, `Auto` says so, but `sigwait` still comes up with something, and
+// `SIGINT` still gets in line behind whatever was already pending, both when
+// it's already there and when it has to wake us up.
+#[test]
+fn out_of_fds() {
+   let _lock = LOCK.lock().unwrap();
+   sigint_workaround(SigintWorkaround::Always);
+   let mut fds = vec![];
+   loop {
+      match unsafe { libc::dup(0) } {
+         -1 => break,
+         fd => fds.push(fd),
+      }
+   }
+   let sigs = [Signal::INT, Signal::USR1, Signal::USR2];
+   let e = Signals::try_new(&sigs).err();
+   assert!(matches!(e, Some(Error::Os { errno, .. }) if errno == libc::EMFILE));
+   let mut s = sigwait::Signals::new(&sigs);
+   for fd in fds {
+      unsafe { libc::close(fd) };
+   }
+
+   for sig in [Signal::USR2, Signal::INT, Signal::USR1] {
+      send_to_thread(unsafe { libc::gettid() }, sig).unwrap();
+   }
+   let got = s.drain().map(|info| info.unwrap().signal()).collect::<Vec<_>>();
+   assert_eq!(got, [Signal::USR2, Signal::INT, Signal::USR1]);
+
+   // Whichever thread ends up running the handler has to ring for us.
+   let sender = thread::spawn(|| {
+      thread::sleep(Duration::from_millis(100));
+      send(unsafe { libc::getpid() }, Signal::INT).unwrap();
+   });
+   assert_eq!(s.next_timeout(Duration::from_secs(5)), Ok(Some(Signal::INT)));
+   sender.join().unwrap();
+   assert_eq!(s.next_now(), Ok(None));
+}
+
+// Handed off to a thread that had nothing blocked, which has to block the lot
+// before it can wait for anything sent to the whole process, and rings the
+// doorbell there from then on. The doorbell itself only stays blocked for as
+// long as it takes to wait. In a child, since the test harness's threads
+// don't have anything blocked either.
+#[test]
+fn other_thread() {
+   let _lock = LOCK.lock().unwrap();
+   in_child(|| {
+      sigint_workaround(SigintWorkaround::Always);
+      let doorbell = Signal::rt(Signal::rt_count() - 1).unwrap();
+      let (tx, rx) = mpsc::channel::<sigwait::Signals>();
+      let waiter = thread::spawn(move || {
+         let mut s = rx.recv().unwrap();
+         assert!(!blocked().contains(Signal::USR1));
+         assert_eq!(s.next_now(), Ok(None));
+         assert!(blocked().contains(Signal::USR1));
+         assert!(!blocked().contains(doorbell));
+         let me = unsafe { libc::getpid() };
+         for sig in [Signal::USR1, Signal::INT] {
+            send(me, sig).unwrap();
+            assert_eq!(s.next_timeout(Duration::from_secs(5)), Ok(Some(sig)));
+         }
+         assert!(!blocked().contains(doorbell));
+      });
+      tx.send(sigwait::Signals::new(&[Signal::INT, Signal::USR1])).unwrap();
+      waiter.join().unwrap();
+   });
+}
+
+// Somebody else has the `SIGINT` eventfd going, which doesn't keep us from
+// hearing about it, and neither of us hears about it twice.
+#[test]
+fn sigint_alongside_eventfd() {
+   let _lock = LOCK.lock().unwrap();
+   sigint_workaround(SigintWorkaround::Always);
+   let tid = unsafe { libc::gettid() };
+   let mut fd = Signals::new(&[Signal::INT]);
+   let mut s = sigwait::Signals::new(&[Signal::INT]);
+   send_to_thread(tid, Signal::INT).unwrap();
+   assert_eq!(s.next_timeout(Duration::from_secs(5)), Ok(Some(Signal::INT)));
+   assert_eq!(fd.next_now(), Ok(None));
+   drop(s);
+   send_to_thread(tid, Signal::INT).unwrap();
+   assert_eq!(fd.next_timeout(Duration::from_secs(5)), Ok(Some(Signal::INT)));
+   assert_eq!(fd.next_now(), Ok(None));
+}
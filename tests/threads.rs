@@ -0,0 +1,43 @@
+use std::sync::mpsc;
+use std::thread;
+
+use macluhan::{Error, Signal, Signals};
+
+// The test harness's own threads were all around long before we were, so
+// there's always somebody to complain about until everyone's been told.
+#[test]
+fn checked() {
+   let (tx, rx) = mpsc::channel();
+   let (done_tx, done_rx) = mpsc::channel::<()>();
+   let straggler = thread::spawn(move || {
+      tx.send(unsafe { libc::gettid() }).unwrap();
+      done_rx.recv().unwrap();
+   });
+   let tid = rx.recv().unwrap();
+
+   let sigs = Signal::USR1.into();
+   assert!(matches!(Signals::try_checked(sigs), Err(Error::Unblocked(_))));
+   let s = Signals::try_from_set(sigs).unwrap();
+   assert!(s.unblocked_threads().unwrap().any(|t| t.unwrap() == tid));
+   // Not every architecture can, in which case there's nothing more to see,
+   // but everything we actually test on had better.
+   let supported = cfg!(any(
+      target_arch = "aarch64",
+      target_arch = "arm",
+      target_arch = "loongarch64",
+      target_arch = "riscv64",
+      target_arch = "x86",
+      target_arch = "x86_64",
+      all(target_arch = "powerpc64", target_env = "gnu"),
+      target_arch = "s390x",
+   ));
+   match s.block_threads() {
+      Err(Error::Unsupported(_)) if !supported => return,
+      r => r.unwrap(),
+   }
+   assert_eq!(s.check_threads(), Ok(()));
+   assert!(Signals::try_checked(sigs).is_ok());
+
+   done_tx.send(()).unwrap();
+   straggler.join().unwrap();
+}
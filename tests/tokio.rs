@@ -0,0 +1,202 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use std::future::{self, Future};
+use std::mem::MaybeUninit;
+use std::pin::pin;
+#[cfg(feature = "stream")]
+use std::pin::Pin;
+use std::ptr;
+use std::sync::Mutex;
+use std::task::Poll;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "stream")]
+use futures_core::Stream;
+use macluhan::{send_to_thread, sigint_workaround, Error, SigSet, SigintWorkaround, Signal};
+use tokio::runtime::{Builder, Runtime};
+
+use common::blocked;
+
+// `sigint_workaround` is for everybody, and so is `SIGINT`.
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn runtime() -> Runtime {
+   Builder::new_current_thread().enable_io().build().unwrap()
+}
+
+fn disposition(sig: Signal) -> usize {
+   let mut act = MaybeUninit::<libc::sigaction>::uninit();
+   unsafe {
+      libc::sigaction(sig.get(), ptr::null(), act.as_mut_ptr());
+      act.assume_init().sa_sigaction
+   }
+}
+
+// There's no timer without tokio's `time` feature, so a thread pokes us once
+// the time's up, and `None` means nobody else did first.
+fn block_on_timeout<T>(rt: &Runtime, fut: impl Future<Output = T>) -> Option<T> {
+   let deadline = Instant::now() + Duration::from_secs(5);
+   let mut fut = pin!(fut);
+   let mut poked = false;
+   rt.block_on(future::poll_fn(|cx| {
+      if let Poll::Ready(x) = fut.as_mut().poll(cx) {
+         return Poll::Ready(Some(x));
+      }
+      if Instant::now() >= deadline {
+         return Poll::Ready(None);
+      }
+      if !poked {
+         let waker = cx.waker().clone();
+         thread::spawn(move || {
+            thread::sleep(deadline - Instant::now());
+            waker.wake();
+         });
+         poked = true;
+      }
+      Poll::Pending
+   }))
+}
+
+// Sent from somewhere else a little later, so that there's actually something
+// to wake up from.
+fn send_later(sig: Signal) -> thread::JoinHandle<()> {
+   let tid = unsafe { libc::gettid() };
+   thread::spawn(move || {
+      thread::sleep(Duration::from_millis(50));
+      send_to_thread(tid, sig).unwrap();
+   })
+}
+
+// Both through the signalfd and, with the workaround, through the `SIGINT`
+// eventfd, which is registered with the reactor separately.
+#[test]
+fn wakeup() {
+   let _lock = LOCK.lock().unwrap();
+   let rt = runtime();
+   for workaround in [SigintWorkaround::Always, SigintWorkaround::Never] {
+      sigint_workaround(workaround);
+      let mut s = macluhan::tokio::Signals::new(&[Signal::INT, Signal::USR1]);
+      for sig in [Signal::USR1, Signal::INT, Signal::USR1] {
+         let sender = send_later(sig);
+         let got = block_on_timeout(&rt, s.next());
+         sender.join().unwrap();
+         assert_eq!(got.map(Result::unwrap), Some(sig), "{workaround:?}");
+      }
+   }
+}
+
+// Same signals sent the same way come out in the same order as they would
+// from the plain old blocking `Signals`, `SIGINT` eventfd and all.
+#[test]
+fn same_order() {
+   let _lock = LOCK.lock().unwrap();
+   sigint_workaround(SigintWorkaround::Always);
+   let rt = runtime();
+   let sigs = [Signal::INT, Signal::USR1, Signal::USR2];
+   let sent = [Signal::USR2, Signal::INT, Signal::USR1];
+   let tid = unsafe { libc::gettid() };
+   let raise = || {
+      for sig in sent {
+         send_to_thread(tid, sig).unwrap();
+      }
+   };
+
+   let mut s = macluhan::Signals::new(&sigs);
+   raise();
+   let sync = s.drain().map(|info| info.unwrap().signal()).collect::<Vec<_>>();
+   drop(s);
+   let mut s = macluhan::tokio::Signals::new(&sigs);
+   raise();
+   let tokio = (0..sent.len())
+      .map(|_| block_on_timeout(&rt, s.next()).unwrap().unwrap())
+      .collect::<Vec<_>>();
+   assert_eq!(sync, [Signal::USR2, Signal::INT, Signal::USR1]);
+   assert_eq!(tokio, sync);
+}
+
+// Inside the runtime, the test harness's threads don't have anything blocked,
+// so we get told no, and everything goes back the way it was.
+#[test]
+fn unblocked_in_runtime() {
+   let _lock = LOCK.lock().unwrap();
+   sigint_workaround(SigintWorkaround::Always);
+   let rt = runtime();
+   let before = blocked();
+   let e = rt.block_on(async { macluhan::tokio::Signals::try_new(&[Signal::INT, Signal::USR1]) });
+   assert!(matches!(e, Err(Error::Unblocked(_))));
+   assert_eq!(blocked(), before);
+   assert_eq!(blocked() & SigSet::from(Signal::USR1), SigSet::empty());
+   assert_eq!(disposition(Signal::INT), libc::SIG_DFL);
+}
+
+// Same deal for `add`, which leaves us with what we had before.
+#[test]
+fn add_in_runtime() {
+   let _lock = LOCK.lock().unwrap();
+   sigint_workaround(SigintWorkaround::Always);
+   let rt = runtime();
+   let mut s = macluhan::tokio::Signals::new(&[Signal::USR1]);
+   let before = blocked();
+   let new = SigSet::from(Signal::INT).with(Signal::USR2);
+   let e = rt.block_on(async { s.add(&new) });
+   assert!(matches!(e, Err(Error::Unblocked(_))));
+   assert_eq!(s.set(), Signal::USR1.into());
+   assert_eq!(blocked(), before);
+   assert_eq!(disposition(Signal::INT), libc::SIG_DFL);
+   let sender = send_later(Signal::USR1);
+   let got = block_on_timeout(&rt, s.next());
+   sender.join().unwrap();
+   assert_eq!(got.map(Result::unwrap), Some(Signal::USR1));
+}
+
+// `SIGINT` showing up after we've already been polled brings the eventfd
+// along, which has to get registered with the reactor too.
+#[test]
+fn sigint_added_later() {
+   let _lock = LOCK.lock().unwrap();
+   sigint_workaround(SigintWorkaround::Always);
+   let rt = runtime();
+   let mut s = macluhan::tokio::Signals::new(&[Signal::USR1]);
+   for sig in [Signal::USR1, Signal::INT] {
+      let sender = send_later(sig);
+      let got = block_on_timeout(&rt, s.next());
+      sender.join().unwrap();
+      assert_eq!(got.map(Result::unwrap), Some(sig));
+      s.add(&Signal::INT.into()).unwrap();
+   }
+   s.remove(&Signal::INT.into()).unwrap();
+   assert_eq!(disposition(Signal::INT), libc::SIG_DFL);
+}
+
+// Never ends, not even when the fd was readable and then there was nothing to
+// read after all, be it from the readiness that tokio holds onto after a read
+// or from somebody else taking the signal first.
+#[cfg(feature = "stream")]
+#[test]
+fn stream() {
+   let _lock = LOCK.lock().unwrap();
+   sigint_workaround(SigintWorkaround::Never);
+   let rt = runtime();
+   let mut s = macluhan::tokio::Signals::new(&[Signal::USR1, Signal::USR2]);
+   let sender = send_later(Signal::USR1);
+   let got = block_on_timeout(&rt, future::poll_fn(|cx| Pin::new(&mut s).poll_next(cx)));
+   sender.join().unwrap();
+   assert_eq!(got.flatten().map(Result::unwrap), Some(Signal::USR1));
+
+   // Still ready as far as tokio knows, but empty.
+   let once = rt.block_on(future::poll_fn(|cx| Poll::Ready(Pin::new(&mut s).poll_next(cx))));
+   assert!(once.is_pending());
+
+   // Readable by the time the reactor gets to it, but somebody else got there
+   // first, so it takes another signal to get anything out of us.
+   let mut thief = macluhan::Signals::new(&[Signal::USR1]);
+   send_to_thread(unsafe { libc::gettid() }, Signal::USR1).unwrap();
+   assert_eq!(thief.next_now(), Ok(Some(Signal::USR1)));
+   let sender = send_later(Signal::USR2);
+   let got = block_on_timeout(&rt, future::poll_fn(|cx| Pin::new(&mut s).poll_next(cx)));
+   sender.join().unwrap();
+   assert_eq!(got.flatten().map(Result::unwrap), Some(Signal::USR2));
+}
@@ -0,0 +1,33 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use macluhan::{send_to_thread, sigint_workaround, SigintWorkaround, Signal, Signals};
+
+// Both with and without the `SIGINT` eventfd, since that makes for two fds to
+// wait on instead of one, and the timeout has to hold up either way.
+#[test]
+fn timeouts() {
+   for workaround in [SigintWorkaround::Always, SigintWorkaround::Never] {
+      sigint_workaround(workaround);
+      let mut s = Signals::new(&[Signal::INT, Signal::USR1]);
+      assert_eq!(s.next_now(), Ok(None));
+      let start = Instant::now();
+      assert_eq!(s.next_timeout(Duration::from_millis(50)), Ok(None));
+      assert!(start.elapsed() >= Duration::from_millis(50));
+      assert_eq!(s.next_timeout(Duration::ZERO), Ok(None));
+
+      let tid = unsafe { libc::gettid() };
+      let sender = thread::spawn(move || {
+         thread::sleep(Duration::from_millis(50));
+         send_to_thread(tid, Signal::INT).unwrap();
+         // Anything that's already pending by the time the `SIGINT` handler
+         // gets to run counts as having come first.
+         thread::sleep(Duration::from_millis(50));
+         send_to_thread(tid, Signal::USR1).unwrap();
+      });
+      assert_eq!(s.next_timeout(Duration::from_secs(5)), Ok(Some(Signal::INT)));
+      sender.join().unwrap();
+      assert_eq!(s.next(), Some(Signal::USR1));
+      assert_eq!(s.next_now(), Ok(None));
+   }
+}